@@ -5,6 +5,7 @@ import chroma from 'chroma-js'
 
 const foodSize = 2
 const foodColor = chroma(sougy.green).darken(1).saturate(0.2).num()
+const carrionColor = chroma(sougy.lightBrown).num()
 
 const foodGeometry = new THREE.SphereGeometry( foodSize, 64, 64 )
 const foodMaterial = new THREE.MeshLambertMaterial({
@@ -12,6 +13,11 @@ const foodMaterial = new THREE.MeshLambertMaterial({
   , opacity: 0
   , color: foodColor
 })
+const carrionMaterial = new THREE.MeshLambertMaterial({
+  transparent: true
+  , opacity: 0
+  , color: carrionColor
+})
 
 export default {
   name: 'food'
@@ -30,8 +36,7 @@ export default {
   , created(){
     this.beforeDraw(() => {
       let step = this.getStep()
-      let isEaten = step >= this.food.status.Eaten
-      this.v3object.visible = !isEaten
+      this.v3object.visible = this.isAvailableAt(step)
 
       let material = this.v3object.material
       material.opacity = THREE.Math.lerp(material.opacity, 1, 0.1)
@@ -39,8 +44,22 @@ export default {
     })
   }
   , methods: {
-    createObject(){
-      this.v3object = new THREE.Mesh( foodGeometry, foodMaterial )
+    isAvailableAt(step){
+      let history = this.food.status_history
+      if ( !history ){
+        return !(step >= this.food.status.Eaten)
+      }
+      // replay spawns, eats and decays up to this step
+      let last = null
+      for ( let [at, event] of history ){
+        if ( at > step ){ break }
+        last = event
+      }
+      return last === 'Spawned'
+    }
+    , createObject(){
+      let material = this.food.kind === 'carrion' ? carrionMaterial : foodMaterial
+      this.v3object = new THREE.Mesh( foodGeometry, material )
       this.autoClean = false
     }
     , updateObjects(){
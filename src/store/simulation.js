@@ -38,7 +38,10 @@ const initialState = {
     seed: 118
     , food_per_generation: [[0, 50]]
     , max_generations: 50
-    , size: 500
+    , stage: {
+      shape: 'square'
+      , size: 500
+    }
     , preset: {
       name: 'default'
       , options: {
@@ -79,6 +82,8 @@ const initialState = {
   }
 
   , canContinue: true
+  // problems with the config, by path. eg: { path: 'creatures[0].count', message: 'must be at least 1' }
+  , diagnostics: []
   , statistics: null
   , currentGenerationIndex: -1
   , getCurrentGeneration: () => null
@@ -107,22 +112,9 @@ function sanitizeConfig( cfg ){
   }, {})
 }
 
+// anything left out takes its default
 function getCreatureTemplate( creatureProps = DEFAULT_CREATURE_PROPS ){
-  let props = sanitizeConfig(creatureProps)
-
-  return {
-    state: 'ACTIVE'
-    , foods_eaten: []
-    , age: 0
-    , energy_consumed: 0
-    // gets overridden
-    , pos: [0, 0]
-    , home_pos: [0, 0]
-    , movement_history: [[0, 0]]
-    , status_history: []
-    , id: '00000000000000000000000000000000'
-    , ...props
-  }
+  return sanitizeConfig(creatureProps)
 }
 
 function getCreatureConfigs(preset, cfgs){
@@ -159,6 +151,7 @@ export const simulation = {
     , currentGenerationIndex: (state, getters, rootState) =>
         rootState.route ? +rootState.route.params.generationIndex - 1 : 0
     , statistics: state => state.statistics
+    , diagnostics: state => state.diagnostics
 
     , traits: state => getTraitsForPreset(state.config.preset.name)
     , traitColors: (state, getters) => getters.getTraitColors(getters.traits)
@@ -187,10 +180,14 @@ export const simulation = {
 
       commit('start', true)
       try {
-        await worker.initSimulation(
-          state.config
-          , getCreatureConfigs(state.config.preset.name, state.creatureConfigs)
-        )
+        let creatureConfigs = getCreatureConfigs(state.config.preset.name, state.creatureConfigs)
+        let diagnostics = await worker.validateConfig(state.config, creatureConfigs)
+        commit('setDiagnostics', diagnostics)
+        if ( diagnostics.length ){
+          throw new Error(diagnostics.map(d => `${d.path}: ${d.message}`).join('\n'))
+        }
+
+        await worker.initSimulation(state.config, creatureConfigs)
 
         await worker.advanceSimulation(preload)
         commit('setMeta', {
@@ -254,6 +251,18 @@ export const simulation = {
     , setConfig({ commit }, config = {}){
       commit('setConfig', _cloneDeep(config))
     }
+    // the current config as a scenario file. format is 'json' or 'toml'
+    , async exportScenario({ state }, format = 'json'){
+      let creatureConfigs = getCreatureConfigs(state.config.preset.name, state.creatureConfigs)
+      return worker.saveScenario(state.config, creatureConfigs, format)
+    }
+    // any version of scenario file. Its creatures replace the active ones, by species
+    , async importScenario({ commit }, { text, format = 'json' }){
+      let { creatures, ...config } = await worker.loadScenario(text, format)
+      commit('setConfig', config)
+      commit('setCreatures', creatures)
+      commit('setDiagnostics', [])
+    }
     , setCreatureConfig({ commit }, config = {}){
       commit('setCreatureConfig', _cloneDeep(config))
     }
@@ -284,6 +293,9 @@ export const simulation = {
         state[k] = meta[k]
       })
     }
+    , setDiagnostics(state, diagnostics){
+      state.diagnostics = diagnostics
+    }
     , setStatistics(state, stats){
       state.statistics = Object.freeze(stats)
     }
@@ -304,6 +316,25 @@ export const simulation = {
         , ...cfg
       }
     }
+    , setCreatures(state, creatures){
+      let creatureConfigs = {}
+      Object.keys(state.creatureConfigs).forEach(species => {
+        creatureConfigs[species] = { ...state.creatureConfigs[species], active: false }
+      })
+
+      creatures.forEach(({ count, template }) => {
+        let species = template.species
+        creatureConfigs[species] = {
+          name: species
+          , ...creatureConfigs[species]
+          , count
+          , active: true
+          , template
+        }
+      })
+
+      state.creatureConfigs = creatureConfigs
+    }
     , setCreatureTemplate(state, cfg){
       let species = cfg.species || 'default'
       state.creatureConfigs[species] = {
@@ -0,0 +1,345 @@
+use serde::{Deserialize, Deserializer, de::{DeserializeOwned, Error}};
+use serde_json::{Value, Map};
+use std::collections::BTreeSet;
+use stage::Stage;
+use super::*;
+
+pub fn default_max_generations() -> u32 { 50 }
+
+// Everything needed to set up a world, the way the cli and the ui give it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldConfig {
+  pub stage : StageConfig,
+  #[serde(deserialize_with = "seed_from_number_or_string")]
+  pub seed : u64,
+  pub food_per_generation : Vec<(f64, f64)>,
+  pub preset : PresetConfig,
+  pub creatures : Vec<RandomCreatureConfig>,
+  #[serde(default)]
+  pub species : BTreeMap<String, SpeciesConfig>,
+  #[serde(default)]
+  pub events : Vec<ScheduledEvent>,
+  #[serde(default = "default_max_generations")]
+  pub max_generations : u32,
+}
+
+// Seeds too big for a toml integer are written as strings
+fn seed_from_number_or_string<'de, D : Deserializer<'de>>(deserializer : D) -> Result<u64, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Seed {
+    Number(u64),
+    Text(String),
+  }
+
+  match Seed::deserialize(deserializer) {
+    Ok(Seed::Number(seed)) => Ok(seed),
+    Ok(Seed::Text(text)) => text.parse().map_err(|_| D::Error::custom(format!("must be a whole number, not \"{}\"", text))),
+    Err(_) => Err(D::Error::custom("must be a whole number")),
+  }
+}
+
+const FIELDS : [&str; 8] = [
+  "stage",
+  "seed",
+  "food_per_generation",
+  "preset",
+  "creatures",
+  "species",
+  "events",
+  "max_generations",
+];
+
+fn parse<T : DeserializeOwned>(value : &Value, path : &str, problems : &mut Vec<Diagnostic>) -> Option<T> {
+  serde_json::from_value(value.clone())
+    .map_err(|e| problems.push(Diagnostic::new(path, e.to_string())))
+    .ok()
+}
+
+fn required<'a>(fields : &'a Map<String, Value>, name : &str, problems : &mut Vec<Diagnostic>) -> Option<&'a Value> {
+  let value = fields.get(name);
+  if value.is_none() {
+    problems.push(Diagnostic::new(name, "is required"));
+  }
+  value
+}
+
+// each item of a list, with its path
+fn items<'a>(value : &'a Value, path : &str, problems : &mut Vec<Diagnostic>) -> Vec<(String, &'a Value)> {
+  match value.as_array() {
+    Some(list) => list.iter().enumerate().map(|(i, v)| (format!("{}[{}]", path, i), v)).collect(),
+    None => {
+      problems.push(Diagnostic::new(path, "must be a list"));
+      vec![]
+    },
+  }
+}
+
+fn food_problems(value : &Value) -> Vec<Diagnostic> {
+  let mut problems = vec![];
+  let path = "food_per_generation";
+  let points : Vec<(f64, f64)> = match parse(value, path, &mut problems) {
+    Some(points) => points,
+    None => return problems,
+  };
+
+  if points.is_empty() {
+    problems.push(Diagnostic::new(path, "needs at least one point, eg: [[0, 50]]"));
+  }
+
+  for (i, w) in points.windows(2).enumerate() {
+    if w[1].0 < w[0].0 {
+      let message = format!("generations must be in order, but {} comes after {}", w[1].0, w[0].0);
+      problems.push(Diagnostic::new(format!("{}[{}]", path, i + 1), message));
+    }
+  }
+
+  for (i, p) in points.iter().enumerate() {
+    if p.1 < 0. {
+      problems.push(Diagnostic::new(format!("{}[{}]", path, i), format!("food must not be negative, not {}", p.1)));
+    }
+  }
+
+  problems
+}
+
+fn preset_problems(value : &Value, stage : Option<&dyn Stage>) -> Vec<Diagnostic> {
+  let mut problems = vec![];
+
+  // each behaviour on its own first, so a bad one is pointed at directly
+  if let Some(cfgs) = value.get("behaviours").and_then(|b| b.as_array()) {
+    for (i, cfg) in cfgs.iter().enumerate() {
+      if let Err(e) = behaviours::describe_behaviour(cfg) {
+        problems.push(Diagnostic::new(format!("preset.behaviours[{}]", i), e));
+      }
+    }
+  }
+
+  if !problems.is_empty() {
+    return problems;
+  }
+
+  let preset : PresetConfig = match parse(value, "preset", &mut problems) {
+    Some(preset) => preset,
+    None => return problems,
+  };
+
+  if !PRESETS.contains(&preset.name.as_str()) {
+    let message = format!("unknown preset `{}`, expected one of: {}", preset.name, PRESETS.join(", "));
+    problems.push(Diagnostic::new("preset.name", message));
+  }
+
+  if preset.name == "home_remove" && !preset.options.contains_key("step") {
+    problems.push(Diagnostic::new("preset.options.step", "is required by the home_remove preset"));
+  }
+
+  // parameters are checked against the stage, so only if it's usable
+  if let (Some(descriptors), Some(stage)) = (&preset.behaviours, stage) {
+    for (i, d) in descriptors.iter().enumerate() {
+      if let Err(e) = behaviours::validate_behaviour(d, stage) {
+        problems.push(Diagnostic::new(format!("preset.behaviours[{}]", i), e));
+      }
+    }
+  }
+
+  if let Some(cfg) = &preset.reproduction {
+    if let Err(e) = behaviours::describe_reproduction(cfg) {
+      problems.push(Diagnostic::new("preset.reproduction", e));
+    }
+  }
+
+  problems
+}
+
+fn creature_problems(value : &Value) -> Vec<Diagnostic> {
+  let mut problems = vec![];
+  let fields = match value.as_object() {
+    Some(fields) => fields,
+    None => return vec![Diagnostic::new("", "must be an object with a count and a template")],
+  };
+
+  match required(fields, "count", &mut problems).map(|c| c.as_u64()) {
+    Some(Some(0)) => problems.push(Diagnostic::new("count", "must be at least 1")),
+    Some(None) => problems.push(Diagnostic::new("count", "must be a whole number")),
+    _ => {},
+  }
+
+  if let Some(template) = required(fields, "template", &mut problems) {
+    let (template, mut template_problems) = CreatureTemplate::parse(template);
+    template_problems.extend(template.problems());
+    problems.extend(template_problems.into_iter().map(|d| d.within("template")));
+  }
+
+  problems
+}
+
+// Species sections can only name species that are in the world somewhere:
+// one of their own, or the species of some creatures or invaders
+pub fn species_problems<'a, I>(species : I, known : &BTreeSet<String>) -> Vec<Diagnostic>
+  where I : IntoIterator<Item = (&'a String, &'a SpeciesConfig)> {
+  species.into_iter()
+    .flat_map(|(name, cfg)| {
+      let path = format!("species.{}", name);
+      cfg.problems(known).into_iter().map(move |d| d.within(&path))
+    })
+    .collect()
+}
+
+// Every problem with a world's config (as `WorldConfig` takes it), and where it is,
+// eg: `creatures[0].template.size: must be more than 0, not -2`.
+// None means it's fine to create a world from
+pub fn validate_config(cfg : &Value) -> Vec<Diagnostic> {
+  let mut problems = vec![];
+  let fields = match cfg.as_object() {
+    Some(fields) => fields,
+    None => return vec![Diagnostic::new("", "must be an object")],
+  };
+
+  for name in fields.keys().filter(|k| !FIELDS.contains(&k.as_str())) {
+    problems.push(Diagnostic::new(name.as_str(), format!("unknown field, expected one of: {}", FIELDS.join(", "))));
+  }
+
+  // first, as other parts are checked against it
+  let stage = required(fields, "stage", &mut problems)
+    .and_then(|v| parse::<StageConfig>(v, "stage", &mut problems))
+    .and_then(|cfg| {
+      let stage_problems = cfg.problems();
+      if !stage_problems.is_empty() {
+        problems.extend(stage_problems.into_iter().map(|d| d.within("stage")));
+        return None;
+      }
+      cfg.build().map_err(|e| problems.push(Diagnostic::new("stage", e))).ok()
+    });
+
+  if let Some(seed) = required(fields, "seed", &mut problems) {
+    if let Err(e) = seed_from_number_or_string(seed.clone()) {
+      problems.push(Diagnostic::new("seed", e.to_string()));
+    }
+  }
+
+  if let Some(food) = required(fields, "food_per_generation", &mut problems) {
+    problems.extend(food_problems(food));
+  }
+
+  if let Some(preset) = required(fields, "preset", &mut problems) {
+    problems.extend(preset_problems(preset, stage.as_deref()));
+  }
+
+  // every species there is, to check the species section against
+  let mut known = BTreeSet::new();
+
+  if let Some(creatures) = required(fields, "creatures", &mut problems) {
+    for (path, creature) in items(creatures, "creatures", &mut problems) {
+      problems.extend(creature_problems(creature).into_iter().map(|d| d.within(&path)));
+      if let Some(template) = creature.get("template") {
+        known.insert(CreatureTemplate::parse(template).0.species);
+      }
+    }
+  }
+
+  let mut species_cfgs = BTreeMap::new();
+  match fields.get("species").map(|s| s.as_object()) {
+    Some(Some(species)) => for (name, cfg) in species {
+      known.insert(name.clone());
+      if let Some(cfg) = parse::<SpeciesConfig>(cfg, &format!("species.{}", name), &mut problems) {
+        species_cfgs.insert(name.clone(), cfg);
+      }
+    },
+    Some(None) => problems.push(Diagnostic::new("species", "must be an object of species, by name")),
+    None => {},
+  }
+
+  if let Some(events) = fields.get("events") {
+    for (path, event) in items(events, "events", &mut problems) {
+      let event = parse::<ScheduledEvent>(event, &path, &mut problems);
+      if let Some(species) = event.as_ref().and_then(|e| e.species()) {
+        known.insert(species.to_string());
+      }
+      if let (Some(event), Some(stage)) = (event, &stage) {
+        problems.extend(event.problems(&**stage).into_iter().map(|d| d.within(&path)));
+      }
+    }
+  }
+
+  problems.extend(species_problems(&species_cfgs, &known));
+
+  if let Some(max) = fields.get("max_generations") {
+    parse::<u32>(max, "max_generations", &mut problems);
+  }
+
+  problems
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn world(stage : Value, species : Value) -> Value {
+    serde_json::json!({
+      "stage": stage,
+      "seed": 1,
+      "food_per_generation": [[0, 50]],
+      "preset": { "name": "default" },
+      "creatures": [
+        { "count": 10, "template": { "species": "rabbit" } },
+        { "count": 2, "template": { "species": "fox" } },
+      ],
+      "species": species,
+      "events": [{ "generation": 3, "type": "invaders", "count": 2, "template": { "species": "wolf" } }],
+    })
+  }
+
+  // where the problems are, with this as the stage
+  fn stage_problems(stage : Value) -> Vec<String> {
+    validate_config(&world(stage, serde_json::json!({}))).into_iter().map(|d| d.path).collect()
+  }
+
+  fn species_problems(species : Value) -> Vec<String> {
+    let cfg = world(serde_json::json!({ "shape": "square", "size": 500 }), species);
+    validate_config(&cfg).into_iter().map(|d| d.path).collect()
+  }
+
+  #[test]
+  fn bad_stage_dimensions_are_reported_where_they_are() {
+    assert!(stage_problems(serde_json::json!({ "shape": "square", "size": 500 })).is_empty());
+    assert_eq!(stage_problems(serde_json::json!({ "shape": "square", "size": 0 })), vec!["stage.size"]);
+    assert_eq!(stage_problems(serde_json::json!({ "shape": "torus", "size": -1 })), vec!["stage.size"]);
+    assert_eq!(stage_problems(serde_json::json!({ "shape": "circle", "radius": -5 })), vec!["stage.radius"]);
+    assert_eq!(stage_problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [1, 1]] })), vec!["stage.vertices"]);
+  }
+
+  #[test]
+  fn species_can_only_name_species_there_are() {
+    let species = serde_json::json!({
+      "fox": { "diet": { "prey": ["rabbit", "wolf"] }, "predators": ["bear"] },
+      "rabbit": { "predators": ["fox", "foxes"], "interactions": { "rabit": "compete", "rabbit": "compete" } },
+      "bear": {},
+    });
+    assert!(species_problems(serde_json::json!({ "fox": { "diet": { "prey": ["rabbit", "wolf"] } } })).is_empty());
+    assert_eq!(species_problems(species.clone()), vec!["species.rabbit.predators[1]", "species.rabbit.interactions.rabit"]);
+
+    let cfg : WorldConfig = serde_json::from_value(world(serde_json::json!({ "shape": "square", "size": 500 }), species)).unwrap();
+    assert!(matches!(World::from_config(&cfg), Err(SimError::InvalidConfig(_))));
+  }
+
+  #[test]
+  fn a_bad_behaviour_stack_says_which_behaviour() {
+    let mut cfg = world(serde_json::json!({ "shape": "square", "size": 500 }), serde_json::json!({}));
+    cfg["preset"]["behaviours"] = serde_json::json!([
+      { "name": "BasicMove" },
+      { "name": "Predation", "size_ratio": "big" },
+      { "name": "Teleport" },
+    ]);
+    let paths : Vec<_> = validate_config(&cfg).into_iter().map(|d| d.path).collect();
+    assert_eq!(paths, vec!["preset.behaviours[1]", "preset.behaviours[2]"]);
+
+    let e = serde_json::from_value::<WorldConfig>(cfg).unwrap_err().to_string();
+    assert!(e.starts_with("behaviours[1]: bad parameters for behaviour `Predation`"), "{}", e);
+
+    // fine on its own, but not on this stage
+    let mut cfg = world(serde_json::json!({ "shape": "square", "size": 500 }), serde_json::json!({}));
+    cfg["preset"]["behaviours"] = serde_json::json!([{ "name": "EdgeHome", "disabled_edges": [7] }]);
+    let paths : Vec<_> = validate_config(&cfg).into_iter().map(|d| d.path).collect();
+    assert_eq!(paths, vec!["preset.behaviours[0]"]);
+  }
+}
@@ -0,0 +1,168 @@
+use super::*;
+use wasm_bindgen::prelude::*;
+
+// Exposed to javascript as `World`. The shape of the stage
+// is picked by the stage config, eg: `{ shape: 'circle', radius: 250 }`
+#[wasm_bindgen(js_name = World)]
+pub struct JsWorld(World);
+
+// errors reach javascript as their message
+impl From<SimError> for JsValue {
+  fn from(e : SimError) -> JsValue {
+    JsValue::from_str(&e.to_string())
+  }
+}
+
+fn from_js<T : serde::de::DeserializeOwned>(value : &JsValue, name : &str) -> Result<T, JsValue> {
+  value.into_serde().map_err(|e| SimError::InvalidConfig(format!("{}: {}", name, e)).into())
+}
+
+fn to_js<T : serde::Serialize + ?Sized>(value : &T) -> Result<JsValue, JsValue> {
+  JsValue::from_serde(value).map_err(|e| SimError::Serialization(e.to_string()).into())
+}
+
+#[wasm_bindgen(js_class = World)]
+impl JsWorld {
+  pub fn new(stage_cfg : &JsValue, seed : u32, food_per_generation : &JsValue, preset_cfg : &JsValue) -> Result<JsWorld, JsValue> {
+    let stage_cfg = from_js::<StageConfig>(stage_cfg, "stage")?;
+    let food_per_generation = from_js::<Vec<(f64, f64)>>(food_per_generation, "food_per_generation")?;
+    let preset_cfg = from_js(preset_cfg, "preset")?;
+
+    Ok(JsWorld(World::new(&stage_cfg, seed as u64, &food_per_generation, &preset_cfg)?))
+  }
+
+  pub fn add_creatures(&mut self, creature_cfg : &JsValue) -> Result<(), JsValue> {
+    let creature_cfg : RandomCreatureConfig = from_js(creature_cfg, "creatures")?;
+    self.0.add_creatures(&creature_cfg)?;
+
+    Ok(())
+  }
+
+  // eg: `{ fox: { diet: { plants: false, prey: ['rabbit'] } } }`
+  pub fn set_species(&mut self, species : &JsValue) -> Result<(), JsValue> {
+    let species = from_js(species, "species")?;
+    self.0.set_species(species);
+
+    Ok(())
+  }
+
+  // eg: `[{ generation: 10, type: 'food_level', food: 20 }]`
+  pub fn set_events(&mut self, events : &JsValue) -> Result<(), JsValue> {
+    let events = from_js(events, "events")?;
+    self.0.set_events(events)?;
+
+    Ok(())
+  }
+
+  pub fn schedule_invasion(&mut self, generation : usize, invasion : &JsValue) -> Result<(), JsValue> {
+    let invasion = from_js(invasion, "invasion")?;
+    self.0.schedule_invasion(generation, invasion)?;
+
+    Ok(())
+  }
+
+  // snapshots are passed around as json strings so that
+  // they survive the trip through javascript exactly
+  pub fn restore(snapshot : &str) -> Result<JsWorld, JsValue> {
+    let snapshot = serde_json::from_str(snapshot).map_err(|e| SimError::Serialization(e.to_string()))?;
+
+    Ok(JsWorld(World::restore(snapshot)?))
+  }
+
+  pub fn snapshot(&self) -> Result<String, JsValue> {
+    serde_json::to_string(&self.0.snapshot()).map_err(|e| SimError::Serialization(e.to_string()).into())
+  }
+
+  pub fn run(&mut self, max_generations_to_run : u32) -> Result<(), JsValue> {
+    self.0.run(max_generations_to_run)?;
+
+    Ok(())
+  }
+
+  // for watching a generation live, a few steps at a time
+  pub fn start_generation(&mut self) -> Result<(), JsValue> {
+    self.0.start_generation()?;
+
+    Ok(())
+  }
+
+  pub fn advance(&mut self, steps : usize) -> Result<bool, JsValue> {
+    Ok(self.0.advance(steps)?)
+  }
+
+  // eg: `{ type: 'kill_creature', id: '...' }`. Returns the id of anything created
+  pub fn intervene(&mut self, intervention : &JsValue) -> Result<JsValue, JsValue> {
+    let intervention = from_js(intervention, "intervention")?;
+    let created = self.0.intervene(intervention)?;
+
+    to_js(&created)
+  }
+
+  pub fn get_generation_in_progress(&self) -> Result<JsValue, JsValue> {
+    to_js(&self.0.get_generation_in_progress())
+  }
+
+  pub fn can_continue(&self) -> bool {
+    self.0.can_continue()
+  }
+
+  pub fn get_results(&self) -> Result<JsValue, JsValue> {
+    to_js(&self.0.get_results())
+  }
+
+  pub fn get_generation(&self, index : usize) -> Result<JsValue, JsValue> {
+    to_js(self.0.get_generation(index)?)
+  }
+
+  pub fn get_statistics(&self, species_filter : Option<String>) -> Result<JsValue, JsValue> {
+    to_js(&self.0.get_statistics(species_filter))
+  }
+
+  pub fn get_ancestors(&self, id : &str) -> Result<JsValue, JsValue> {
+    let id = Uuid::parse_str(id).map_err(|e| e.to_string())?;
+    to_js(&self.0.get_ancestors(&id))
+  }
+
+  pub fn get_descendants(&self, id : &str) -> Result<JsValue, JsValue> {
+    let id = Uuid::parse_str(id).map_err(|e| e.to_string())?;
+    to_js(&self.0.get_descendants(&id))
+  }
+
+  pub fn get_surviving_lineages(&self) -> Result<JsValue, JsValue> {
+    to_js(&self.0.get_surviving_lineages())
+  }
+
+  pub fn get_newick(&self) -> String {
+    self.0.get_newick()
+  }
+
+  pub fn get_invasions(&self) -> Result<JsValue, JsValue> {
+    to_js(&self.0.get_invasions())
+  }
+}
+
+// Every problem with a world's config, with where it is, eg:
+// `[{ path: 'creatures[0].template.size', message: 'must be more than 0, not -2' }]`
+#[wasm_bindgen(js_name = validate_config)]
+pub fn validate_world_config(cfg : &JsValue) -> Result<JsValue, JsValue> {
+  let cfg : serde_json::Value = from_js(cfg, "config")?;
+  to_js(&validate_config(&cfg))
+}
+
+fn scenario_format(format : &str) -> Result<ScenarioFormat, JsValue> {
+  ScenarioFormat::from_name(format)
+    .ok_or_else(|| SimError::InvalidConfig(format!("unknown scenario format `{}`, expected json or toml", format)).into())
+}
+
+// A scenario (of any version) as the config it gives, eg: `load_scenario(text, 'toml')`
+#[wasm_bindgen(js_name = load_scenario)]
+pub fn load_world_scenario(text : &str, format : &str) -> Result<JsValue, JsValue> {
+  to_js(&load_scenario(text, scenario_format(format)?)?)
+}
+
+// A world's config as a scenario of the current version
+#[wasm_bindgen(js_name = save_scenario)]
+pub fn save_world_scenario(cfg : &JsValue, format : &str) -> Result<String, JsValue> {
+  let cfg : WorldConfig = from_js(cfg, "config")?;
+  Ok(save_scenario(&cfg, scenario_format(format)?)?)
+}
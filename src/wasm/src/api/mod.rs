@@ -1,83 +1,170 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
+use uuid::Uuid;
 use crate::{RunningStatistics, RunningStatisticsResults};
-use wasm_bindgen::prelude::*;
 use super::*;
 use simulation::*;
 use creature::*;
-use behaviours::{BasicReproductionBehaviour, StepBehaviour};
+use stage::StageConfig;
+use behaviours::{StepBehaviour, Pairing};
 
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RandomCreatureConfig {
   pub count : usize,
-  pub template : Creature,
+  pub template : CreatureTemplate,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SimulationResults {
-  generations: Vec<Generation>
+  pub generations: Vec<Generation>
 }
 
-#[derive(Serialize, Deserialize)]
+// How well an invading group did
+#[derive(Debug, Clone, Serialize)]
+pub struct InvasionReport {
+  pub group : String,
+  // the generation they arrived in
+  pub generation : usize,
+  pub founders : usize,
+  // fraction of each generation from then on that they or their descendants made up
+  pub lineage_share : Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresetConfig {
-  name: String,
-  options: HashMap<String, f64>,
+  pub name: String,
+  #[serde(default)]
+  pub options: HashMap<String, f64>,
+  // step behaviours to use instead of the usual ones, in order, by name
+  // with their parameters. eg: `[{ "name": "Predation", "size_ratio": 0.8 }, ...]`
+  #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "behaviours::deserialize_behaviours")]
+  pub behaviours: Option<Vec<behaviours::BehaviourDescriptor>>,
+  // eg: `{ "name": "Sexual", "pairing": "random", "recombination": "pick" }`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reproduction: Option<serde_json::Value>,
+}
+
+// every preset, by name
+pub const PRESETS : [&str; 2] = [
+  "default",
+  "home_remove",
+];
+
+// stages without edges (eg: torus) need somewhere else to call home.
+// A nest can be given with the `nest_x` and `nest_y` preset options
+fn home_behaviour( sim : &Simulation, preset : &PresetConfig ) -> Box<dyn StepBehaviour> {
+  let nest = match (preset.options.get("nest_x"), preset.options.get("nest_y")) {
+    (Some(x), Some(y)) => Some(na::Point2::new(*x, *y)),
+    _ => None,
+  };
+
+  if nest.is_some() || sim.stage.get_edges().is_empty() {
+    Box::new(behaviours::NestHomeBehaviour { nest })
+  } else {
+    Box::new(behaviours::EdgeHomeBehaviour { disabled_edges: vec![] })
+  }
 }
 
-fn primer_behaviours() -> Vec<Box<dyn StepBehaviour>>{
+fn primer_behaviours( home : Box<dyn StepBehaviour> ) -> Vec<Box<dyn StepBehaviour>>{
   vec![
     Box::new(behaviours::BasicMoveBehaviour),
     Box::new(behaviours::WanderBehaviour),
-    Box::new(behaviours::CannibalismBehaviour { size_ratio: 0.8 }),
+    Box::new(behaviours::PredationBehaviour { size_ratio: 0.8 }),
     Box::new(behaviours::ScavengeBehaviour),
     Box::new(behaviours::SatisfiedBehaviour),
-    Box::new(behaviours::EdgeHomeBehaviour { disabled_edges: vec![] }),
+    home,
     Box::new(behaviours::StarveBehaviour),
   ]
 }
 
-fn use_preset( sim : &mut Simulation, preset : &PresetConfig ){
-  let mut behaviours = match preset.name.as_str() {
+fn use_preset( sim : &mut Simulation, preset : &PresetConfig ) -> SimResult<()> {
+  if !PRESETS.contains(&preset.name.as_str()) {
+    return Err(SimError::InvalidConfig(format!("unknown preset `{}`, expected one of: {}", preset.name, PRESETS.join(", "))));
+  }
+
+  sim.energy = energy_config(preset);
+  let mut behaviours = match &preset.behaviours {
+    Some(descriptors) => behaviours::build_behaviours(descriptors, &*sim.stage)
+      .map_err(|e| SimError::InvalidConfig(format!("preset {}", e)))?,
+    None => primer_behaviours(home_behaviour(sim, preset)),
+  };
+
+  match preset.name.as_str() {
     "home_remove" => {
-      let step_at_home_change = preset.options["step"] as usize;
-      sim.add_generation_callback(move |sim| {
-        if sim.generations.len() == step_at_home_change {
-          sim.behaviours[6] = Box::new(behaviours::EdgeHomeBehaviour {
-            disabled_edges: vec![0, 1, 2],
-          });
-        }
+      let step_at_home_change = preset.options.get("step")
+        .ok_or_else(|| SimError::InvalidConfig("preset home_remove needs a `step` option".to_string()))?;
+      let step_at_home_change = *step_at_home_change as usize;
+      sim.timeline.push(ScheduledEvent {
+        generation: step_at_home_change,
+        step: None,
+        event: EnvironmentEvent::DisableEdges { edges: vec![0, 1, 2] },
       });
-
-      primer_behaviours()
     },
     _ => {
       // default
-      primer_behaviours()
     }
   };
 
   for b in behaviours.drain(0..behaviours.len()) {
     sim.add_behaviour(b);
   }
+
+  if let Some(cfg) = &preset.reproduction {
+    let reproduction = behaviours::describe_reproduction(cfg)
+      .map_err(|e| SimError::InvalidConfig(format!("preset reproduction: {}", e)))?;
+    sim.set_reproduction_behaviour(reproduction.build());
+  } else if is_set(preset, "sexual") {
+    // two parents instead of one, with the `sexual` option
+    sim.set_reproduction_behaviour(Box::new(behaviours::SexualReproductionBehaviour {
+      pairing: if is_set(preset, "random_pairing") { Pairing::Random } else { Pairing::Nearest },
+      recombination: if is_set(preset, "pick_traits") { Recombination::Pick } else { Recombination::Blend },
+    }));
+  }
+
+  Ok(())
 }
 
+// any of the energy settings can be overridden by a preset option of the same name
+fn energy_config( preset : &PresetConfig ) -> EnergyConfig {
+  let defaults = EnergyConfig::default();
+  let option = |name : &str, default : f64| preset.options.get(name).cloned().unwrap_or(default);
+
+  EnergyConfig {
+    food_energy: option("food_energy", defaults.food_energy),
+    prey_energy_per_size: option("prey_energy_per_size", defaults.prey_energy_per_size),
+    survival_energy: option("survival_energy", defaults.survival_energy),
+    reproduction_energy: option("reproduction_energy", defaults.reproduction_energy),
+  }
+}
+
+// flag-like preset options are on when non zero
+fn is_set( preset : &PresetConfig, option : &str ) -> bool {
+  preset.options.get(option).is_some_and(|v| *v != 0.)
+}
+
+// statistics of every trait, by name
+pub type TraitStatistics = BTreeMap<String, RunningStatisticsResults>;
+
 #[derive(Serialize)]
-struct GenerationStatistics {
+pub struct GenerationStatistics {
   population: usize,
 
   // traits
-  speed : RunningStatisticsResults,
-  size : RunningStatisticsResults,
-  sense_range : RunningStatisticsResults,
-  reach : RunningStatisticsResults,
-  life_span : RunningStatisticsResults,
+  #[serde(flatten)]
+  traits : TraitStatistics,
+  mutation_variance : TraitStatistics,
 
   // longevity
   age : RunningStatisticsResults,
   age_at_death : RunningStatisticsResults,
   // food related
+  energy_eaten : RunningStatisticsResults,
   food_balls_available: u32,
   food_balls_eaten: u32,
   creatures_eaten: u32,
+  carrion_eaten: u32,
+
+  // done from outside while it was in progress
+  interventions: u32,
 
   // births/deaths
   births: u32,
@@ -85,44 +172,51 @@ struct GenerationStatistics {
 }
 
 #[derive(Serialize)]
-struct SimulationStatistics {
+pub struct SimulationStatistics {
   num_generations: usize,
 
   population : RunningStatisticsResults,
-  speed : RunningStatisticsResults,
-  size : RunningStatisticsResults,
-  sense_range : RunningStatisticsResults,
-  reach : RunningStatisticsResults,
-  life_span : RunningStatisticsResults,
+  #[serde(flatten)]
+  traits : TraitStatistics,
+  mutation_variance : TraitStatistics,
   age : RunningStatisticsResults,
   age_at_death : RunningStatisticsResults,
 
   generation_statistics: Vec<GenerationStatistics>,
 }
 
-pub fn get_statistics(sim : &Simulation, species_filter : Option<String>) -> JsValue {
+fn push_traits<F>(stats : &mut BTreeMap<String, RunningStatistics>, creature : &Creature, f : F)
+where F : Fn(&Gene) -> f64 {
+  for (name, gene) in creature.get_genome().iter() {
+    stats.entry(name.clone())
+      .or_insert_with(RunningStatistics::new)
+      .push(f(gene));
+  }
+}
+
+fn trait_results(stats : BTreeMap<String, RunningStatistics>) -> TraitStatistics {
+  stats.into_iter().map(|(name, s)| (name, s.as_results())).collect()
+}
+
+pub fn get_statistics(sim : &Simulation, species_filter : Option<String>) -> SimulationStatistics {
   let mut population = RunningStatistics::new();
-  let mut tot_speed = RunningStatistics::new();
-  let mut tot_size = RunningStatistics::new();
-  let mut tot_sense_range = RunningStatistics::new();
-  let mut tot_reach = RunningStatistics::new();
-  let mut tot_life_span = RunningStatistics::new();
+  let mut tot_traits = BTreeMap::new();
+  let mut tot_variance = BTreeMap::new();
   let mut tot_age = RunningStatistics::new();
   let mut tot_age_at_death = RunningStatistics::new();
 
   // every generation
   let generation_statistics = sim.generations.iter().map(|g| {
-    let mut speed = RunningStatistics::new();
-    let mut size = RunningStatistics::new();
-    let mut sense_range = RunningStatistics::new();
-    let mut reach = RunningStatistics::new();
-    let mut life_span = RunningStatistics::new();
+    let mut traits = BTreeMap::new();
+    let mut variance = BTreeMap::new();
     let mut age = RunningStatistics::new();
     let mut age_at_death = RunningStatistics::new();
+    let mut energy_eaten = RunningStatistics::new();
 
     let mut food_balls_eaten = 0;
     let mut creatures_eaten = 0;
-    let food_balls_available = g.food.len() as u32;
+    let mut carrion_eaten = 0;
+    let food_balls_available = g.food.iter().filter(|f| f.kind == FoodKind::FoodBall).count() as u32;
 
     let mut births = 0;
     let mut deaths = 0;
@@ -134,13 +228,10 @@ pub fn get_statistics(sim : &Simulation, species_filter : Option<String>) -> JsV
     }).for_each(|c|{
       count += 1;
 
-      let t = c.get_traits();
-      speed.push(t["speed"].0);
-      size.push(t["size"].0);
-      sense_range.push(t["sense_range"].0);
-      reach.push(t["reach"].0);
-      life_span.push(t["life_span"].0);
+      push_traits(&mut traits, c, |g| g.value);
+      push_traits(&mut variance, c, |g| g.variance);
       age.push(c.age as f64);
+      energy_eaten.push(c.energy_eaten);
 
       for eaten in &c.foods_eaten {
         match eaten.2.as_str() {
@@ -150,11 +241,14 @@ pub fn get_statistics(sim : &Simulation, species_filter : Option<String>) -> JsV
           "creature" => {
             creatures_eaten += 1;
           },
+          "carrion" => {
+            carrion_eaten += 1;
+          },
           _ => {}
         }
       }
 
-      if BasicReproductionBehaviour::will_reproduce(&c) {
+      if sim.energy.will_reproduce(c) {
         births += 1;
       }
 
@@ -165,53 +259,58 @@ pub fn get_statistics(sim : &Simulation, species_filter : Option<String>) -> JsV
       }
 
 
-      tot_speed.push(t["speed"].0);
-      tot_size.push(t["size"].0);
-      tot_sense_range.push(t["sense_range"].0);
-      tot_reach.push(t["reach"].0);
-      tot_life_span.push(t["life_span"].0);
+      push_traits(&mut tot_traits, c, |g| g.value);
+      push_traits(&mut tot_variance, c, |g| g.variance);
       tot_age.push(c.age as f64);
     });
 
     population.push(count as f64);
 
     GenerationStatistics {
-      speed: speed.as_results(),
-      size: size.as_results(),
-      sense_range: sense_range.as_results(),
-      reach: reach.as_results(),
-      life_span: life_span.as_results(),
+      traits: trait_results(traits),
+      mutation_variance: trait_results(variance),
 
       population: count,
       age: age.as_results(),
       age_at_death: age_at_death.as_results(),
 
+      energy_eaten: energy_eaten.as_results(),
       food_balls_available,
       food_balls_eaten,
       creatures_eaten,
+      carrion_eaten,
+
+      interventions: g.interventions.len() as u32,
 
       births,
       deaths,
     }
   }).collect();
 
-  let result = SimulationStatistics {
+  SimulationStatistics {
     num_generations: sim.generations.len(),
 
     population: population.as_results(),
-    speed: tot_speed.as_results(),
-    size: tot_size.as_results(),
-    sense_range: tot_sense_range.as_results(),
-    reach: tot_reach.as_results(),
-    life_span: tot_life_span.as_results(),
+    traits: trait_results(tot_traits),
+    mutation_variance: trait_results(tot_variance),
     age: tot_age.as_results(),
     age_at_death: tot_age_at_death.as_results(),
 
     generation_statistics,
-  };
-
-  JsValue::from_serde(&result).unwrap()
+  }
 }
 
-mod square_world;
-pub use square_world::*;
+
+mod config;
+pub use config::*;
+
+mod scenario;
+pub use scenario::*;
+
+mod world;
+pub use world::*;
+
+#[cfg(feature = "wasm")]
+mod js_world;
+#[cfg(feature = "wasm")]
+pub use js_world::*;
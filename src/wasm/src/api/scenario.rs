@@ -0,0 +1,278 @@
+use serde_json::{Value, Map};
+use super::*;
+
+// The version scenarios are saved as. Older ones are migrated up to it
+// when loaded, so they still run (and give the same results) as they did
+pub const SCENARIO_VERSION : u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScenarioFormat {
+  Json,
+  Toml,
+}
+
+impl ScenarioFormat {
+  // from a name or file extension, eg: `toml`
+  pub fn from_name(name : &str) -> Option<Self> {
+    match name.to_lowercase().as_str() {
+      "json" => Some(ScenarioFormat::Json),
+      "toml" => Some(ScenarioFormat::Toml),
+      _ => None,
+    }
+  }
+}
+
+type Migration = fn(&mut Map<String, Value>);
+
+// Each takes a scenario from the version before to this one
+const MIGRATIONS : [(u64, Migration); 2] = [
+  (1, from_unversioned),
+  (2, explicit_traits),
+];
+
+// The first scenarios had no version. They gave a square stage by its `size`,
+// used whole creatures as templates, and called Predation `Cannibalism`
+fn from_unversioned(cfg : &mut Map<String, Value>) {
+  if let Some(size) = cfg.remove("size") {
+    cfg.entry("stage").or_insert_with(|| serde_json::json!({ "shape": "square", "size": size }));
+  }
+
+  const RENAMED : [(&str, &str); 1] = [
+    ("Cannibalism", "Predation"),
+  ];
+
+  let renamed = |name : &mut Value| {
+    if let Some((_, new)) = RENAMED.iter().find(|(old, _)| name.as_str() == Some(*old)) {
+      *name = Value::String(new.to_string());
+    }
+  };
+
+  let strip_state = |template : &mut Value| {
+    if let Some(fields) = template.as_object_mut() {
+      for name in CREATURE_STATE_FIELDS.iter() {
+        fields.remove(*name);
+      }
+    }
+  };
+
+  if let Some(Value::Array(behaviours)) = cfg.get_mut("preset").and_then(|p| p.get_mut("behaviours")) {
+    for name in behaviours.iter_mut().filter_map(|b| b.get_mut("name")) {
+      renamed(name);
+    }
+  }
+
+  if let Some(Value::Array(creatures)) = cfg.get_mut("creatures") {
+    for template in creatures.iter_mut().filter_map(|c| c.get_mut("template")) {
+      strip_state(template);
+    }
+  }
+
+  if let Some(Value::Array(events)) = cfg.get_mut("events") {
+    for event in events.iter_mut() {
+      if let Some(name) = event.get_mut("name") {
+        renamed(name);
+      }
+      if let Some(name) = event.get_mut("behaviour").and_then(|b| b.get_mut("name")) {
+        renamed(name);
+      }
+      if let Some(template) = event.get_mut("template") {
+        strip_state(template);
+      }
+    }
+  }
+}
+
+// Version 1 templates took anything they didn't know as a trait.
+// Now only the usual traits go by name, and the rest go in `traits`.
+// Creature state is still ignored where it is
+fn explicit_traits(cfg : &mut Map<String, Value>) {
+  let move_traits = |template : &mut Value| {
+    let fields = match template.as_object_mut() {
+      Some(fields) => fields,
+      None => return,
+    };
+
+    let custom : Vec<String> = fields.keys()
+      .filter(|k| !["species", "energy", "traits"].contains(&k.as_str()) && !is_usual_trait(k))
+      .filter(|k| !CREATURE_STATE_FIELDS.contains(&k.as_str()))
+      .cloned()
+      .collect();
+    if custom.is_empty() {
+      return;
+    }
+
+    let mut traits = Map::new();
+    for name in custom {
+      if let Some(gene) = fields.remove(&name) {
+        traits.insert(name, gene);
+      }
+    }
+    fields.insert("traits".to_string(), Value::Object(traits));
+  };
+
+  if let Some(Value::Array(creatures)) = cfg.get_mut("creatures") {
+    for template in creatures.iter_mut().filter_map(|c| c.get_mut("template")) {
+      move_traits(template);
+    }
+  }
+
+  if let Some(Value::Array(events)) = cfg.get_mut("events") {
+    for template in events.iter_mut().filter_map(|e| e.get_mut("template")) {
+      move_traits(template);
+    }
+  }
+}
+
+// Bring a scenario of any version up to the current one.
+// The result has no `version`, so it's a config as `WorldConfig` takes it
+pub fn migrate_scenario(cfg : Value) -> SimResult<Value> {
+  let mut cfg = match cfg {
+    Value::Object(fields) => fields,
+    _ => return Err(SimError::InvalidConfig("a scenario must be an object".to_string())),
+  };
+
+  let version = match cfg.remove("version") {
+    None => 0,
+    Some(v) => v.as_u64().ok_or_else(|| SimError::InvalidConfig(format!("version: must be a whole number, not {}", v)))?,
+  };
+
+  if version > SCENARIO_VERSION {
+    let message = format!("version: {} is newer than this can read (up to {})", version, SCENARIO_VERSION);
+    return Err(SimError::InvalidConfig(message));
+  }
+
+  for (_, migrate) in MIGRATIONS.iter().filter(|(to, _)| *to > version) {
+    migrate(&mut cfg);
+  }
+
+  Ok(Value::Object(cfg))
+}
+
+// A scenario of any version, migrated but not yet checked. eg:
+// `version = 1`, `seed = 118`, `[stage]`, `shape = "square"`, ...
+pub fn read_scenario(text : &str, format : ScenarioFormat) -> SimResult<Value> {
+  let cfg : Value = match format {
+    ScenarioFormat::Json => serde_json::from_str(text).map_err(|e| SimError::Serialization(e.to_string()))?,
+    ScenarioFormat::Toml => toml::from_str(text).map_err(|e| SimError::Serialization(e.to_string()))?,
+  };
+
+  migrate_scenario(cfg)
+}
+
+// Read a scenario of any version, checking all of it
+pub fn load_scenario(text : &str, format : ScenarioFormat) -> SimResult<WorldConfig> {
+  let cfg = read_scenario(text, format)?;
+  let problems = validate_config(&cfg);
+  if !problems.is_empty() {
+    return Err(problems.into());
+  }
+
+  serde_json::from_value(cfg).map_err(|e| SimError::InvalidConfig(e.to_string()))
+}
+
+#[derive(Serialize)]
+struct VersionedConfig<'a> {
+  version : u64,
+  #[serde(flatten)]
+  config : &'a WorldConfig,
+}
+
+// toml has no null, so missing values are left out instead
+fn without_nulls(value : Value) -> Value {
+  match value {
+    Value::Object(fields) => Value::Object(fields.into_iter()
+      .filter(|(_, v)| !v.is_null())
+      .map(|(k, v)| (k, without_nulls(v)))
+      .collect()),
+    Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+    other => other,
+  }
+}
+
+// Write a config as a scenario of the current version
+pub fn save_scenario(cfg : &WorldConfig, format : ScenarioFormat) -> SimResult<String> {
+  let scenario = VersionedConfig { version: SCENARIO_VERSION, config: cfg };
+  match format {
+    ScenarioFormat::Json => serde_json::to_string_pretty(&scenario).map_err(|e| SimError::Serialization(e.to_string())),
+    ScenarioFormat::Toml => {
+      // toml integers stop at i64::MAX, so bigger seeds are written as strings
+      let mut value = serde_json::to_value(&scenario).map_err(|e| SimError::Serialization(e.to_string()))?;
+      if cfg.seed > i64::MAX as u64 {
+        value["seed"] = Value::String(cfg.seed.to_string());
+      }
+
+      toml::Value::try_from(without_nulls(value))
+        .and_then(|value| toml::to_string(&value))
+        .map_err(|e| SimError::Serialization(e.to_string()))
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn other_traits_move_into_traits() {
+    let old = serde_json::json!({
+      "version": 1,
+      "creatures": [{ "count": 1, "template": { "species": "fox", "speed": [12, 0.5], "stripes": [3, 1], "age": 4 } }],
+      "events": [{ "generation": 2, "type": "invaders", "count": 1, "template": { "stripes": [4, 1] } }],
+    });
+    let cfg = migrate_scenario(old).unwrap();
+    assert_eq!(cfg["creatures"][0]["template"], serde_json::json!({ "species": "fox", "speed": [12, 0.5], "traits": { "stripes": [3, 1] }, "age": 4 }));
+    assert_eq!(cfg["events"][0]["template"], serde_json::json!({ "traits": { "stripes": [4, 1] } }));
+  }
+
+  #[test]
+  fn cannibalism_is_predation_in_old_scenarios_only() {
+    let old = serde_json::json!({
+      "preset": { "name": "default", "behaviours": [{ "name": "Cannibalism", "size_ratio": 0.8 }] },
+    });
+    let cfg = migrate_scenario(old).unwrap();
+    assert_eq!(cfg["preset"]["behaviours"][0]["name"], "Predation");
+
+    let current = serde_json::json!({ "name": "Cannibalism", "size_ratio": 0.8 });
+    assert!(serde_json::from_value::<behaviours::BehaviourDescriptor>(current).is_err());
+  }
+
+  fn scenario(seed : u64) -> WorldConfig {
+    let text = include_str!("../../scenarios/predator_prey.json");
+    WorldConfig { seed, ..load_scenario(text, ScenarioFormat::Json).unwrap() }
+  }
+
+  #[test]
+  fn reads_back_what_it_saves() {
+    for format in [ScenarioFormat::Json, ScenarioFormat::Toml].iter() {
+      for seed in [118, i64::MAX as u64, u64::MAX].iter() {
+        let cfg = scenario(*seed);
+        let text = save_scenario(&cfg, *format).unwrap();
+        let read = load_scenario(&text, *format).unwrap();
+        assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&cfg).unwrap());
+      }
+    }
+  }
+
+  #[test]
+  fn toml_and_json_scenarios_read_the_same() {
+    let json = load_scenario(include_str!("../../scenarios/default.json"), ScenarioFormat::Json).unwrap();
+    let toml = load_scenario(include_str!("../../scenarios/default.toml"), ScenarioFormat::Toml).unwrap();
+    assert_eq!(serde_json::to_value(&toml).unwrap(), serde_json::to_value(&json).unwrap());
+
+    let bad = read_scenario("seed = 1\n[stage\n", ScenarioFormat::Toml);
+    assert!(matches!(bad, Err(SimError::Serialization(_))));
+  }
+
+  #[test]
+  fn seeds_must_be_whole_numbers() {
+    let mut cfg = serde_json::to_value(scenario(1)).unwrap();
+    for seed in [serde_json::json!("118"), serde_json::json!(118)].iter() {
+      cfg["seed"] = seed.clone();
+      assert!(validate_config(&cfg).is_empty());
+    }
+    for seed in [serde_json::json!("lots"), serde_json::json!(-1), serde_json::json!(1.5)].iter() {
+      cfg["seed"] = seed.clone();
+      assert_eq!(validate_config(&cfg).into_iter().map(|d| d.path).collect::<Vec<_>>(), vec!["seed"]);
+    }
+  }
+}
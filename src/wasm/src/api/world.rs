@@ -0,0 +1,407 @@
+use super::*;
+
+// A simulation together with the creatures waiting to be
+// placed into its next generation. This is what the wasm
+// bindings and the native cli both drive.
+pub struct World {
+  pub sim: Simulation,
+  stage: StageConfig,
+  preset: PresetConfig,
+  creatures: Vec<Creature>,
+  // being stepped through, if any
+  in_progress: Option<Generation>,
+}
+
+// Serializable state of a world, which can be resumed
+// later (or elsewhere) and continue exactly as it would have
+#[derive(Serialize, Deserialize)]
+pub struct WorldSnapshot {
+  pub stage: StageConfig,
+  pub preset: PresetConfig,
+  pub creatures: Vec<Creature>,
+  pub simulation: SimulationSnapshot,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub in_progress: Option<Generation>,
+}
+
+impl World {
+  pub fn new(stage_cfg : &StageConfig, seed : u64, food_per_generation : &Vec<(f64, f64)>, preset_cfg : &PresetConfig) -> SimResult<Self> {
+    let problems : Vec<Diagnostic> = stage_cfg.problems().into_iter().map(|d| d.within("stage")).collect();
+    if !problems.is_empty() {
+      return Err(problems.into());
+    }
+
+    let stage = stage_cfg.build().map_err(|e| SimError::InvalidConfig(format!("stage: {}", e)))?;
+    let food_per_generation = Interpolator::new(food_per_generation)
+      .map_err(|e| SimError::InvalidConfig(format!("food_per_generation: {}", e)))?;
+
+    let mut sim = Simulation::new(stage, seed, food_per_generation);
+    sim.stage_config = Some(stage_cfg.clone());
+    sim.food_placement = stage_cfg.food_placement.clone();
+    sim.food_dynamics = stage_cfg.food_dynamics.clone();
+    use_preset(&mut sim, preset_cfg)?;
+
+    Ok(Self {
+      sim,
+      stage: stage_cfg.clone(),
+      preset: preset_cfg.clone(),
+      creatures: vec![],
+      in_progress: None,
+    })
+  }
+
+  // all set up, with its creatures waiting for the first generation
+  pub fn from_config(cfg : &WorldConfig) -> SimResult<Self> {
+    let known = cfg.species.keys().cloned()
+      .chain(cfg.creatures.iter().map(|c| c.template.species.clone()))
+      .chain(cfg.events.iter().filter_map(|e| e.species()).map(String::from))
+      .collect();
+    let problems = species_problems(&cfg.species, &known);
+    if !problems.is_empty() {
+      return Err(problems.into());
+    }
+
+    let mut world = World::new(&cfg.stage, cfg.seed, &cfg.food_per_generation, &cfg.preset)?;
+    world.set_species(cfg.species.clone());
+    world.set_events(cfg.events.clone())?;
+    for (i, creature_cfg) in cfg.creatures.iter().enumerate() {
+      // so the error says which one
+      let path = format!("creatures[{}].template", i);
+      let problems : Vec<Diagnostic> = creature_cfg.template.problems().into_iter().map(|d| d.within(&path)).collect();
+      if !problems.is_empty() {
+        return Err(problems.into());
+      }
+
+      world.add_creatures(creature_cfg)?;
+    }
+
+    Ok(world)
+  }
+
+  pub fn snapshot(&self) -> WorldSnapshot {
+    WorldSnapshot {
+      stage: self.stage.clone(),
+      preset: self.preset.clone(),
+      creatures: self.creatures.clone(),
+      simulation: self.sim.snapshot(),
+      in_progress: self.in_progress.clone(),
+    }
+  }
+
+  pub fn restore(snapshot : WorldSnapshot) -> SimResult<Self> {
+    // the preset re-registers any generation callbacks,
+    // then the snapshot overwrites the rest of the state
+    let food_per_generation = vec![(0., 0.)];
+    let mut world = World::new(&snapshot.stage, 0, &food_per_generation, &snapshot.preset)?;
+    world.sim.restore(snapshot.simulation)?;
+    world.creatures = snapshot.creatures;
+    world.in_progress = snapshot.in_progress;
+    Ok(world)
+  }
+
+  pub fn add_creatures(&mut self, creature_cfg : &RandomCreatureConfig) -> SimResult<()> {
+    let problems = creature_cfg.template.problems();
+    if !problems.is_empty() {
+      return Err(problems.into_iter().map(|d| d.within("template")).collect::<Vec<_>>().into());
+    }
+
+    let count = creature_cfg.count;
+
+    for _i in 0..count {
+      let pos = self.sim.get_random_edge_point();
+      let c = creature_cfg.template.build(self.sim.next_id(), &pos);
+
+      self.creatures.push(c);
+    }
+
+    Ok(())
+  }
+
+  // how each species gets along with the others
+  pub fn set_species(&mut self, species : BTreeMap<String, SpeciesConfig>) {
+    self.sim.ecology = Ecology::new(species);
+  }
+
+  // Changes to the environment, on top of any the preset schedules.
+  // Errors say which event was wrong
+  pub fn set_events(&mut self, events : Vec<ScheduledEvent>) -> SimResult<()> {
+    let next = self.sim.generations.len();
+    let mut problems = vec![];
+    for (i, e) in events.iter().enumerate() {
+      let path = format!("events[{}]", i);
+      if e.generation < next {
+        problems.push(Diagnostic::new(format!("{}.generation", path), format!("generation {} has already happened", e.generation)));
+      }
+      problems.extend(e.problems(&*self.sim.stage).into_iter().map(|d| d.within(&path)));
+    }
+
+    if !problems.is_empty() {
+      return Err(problems.into());
+    }
+
+    self.sim.timeline.extend(events);
+    Ok(())
+  }
+
+  // bring in a group of creatures at the start of a future generation
+  pub fn schedule_invasion(&mut self, generation : usize, invasion : InvasionConfig) -> SimResult<()> {
+    self.set_events(vec![ScheduledEvent {
+      generation,
+      step: None,
+      event: EnvironmentEvent::Invaders(invasion),
+    }])
+  }
+
+  // Finishes off one that's being stepped through first. A runaway
+  // generation is left in progress, where it got to
+  pub fn run(&mut self, max_generations_to_run : u32) -> SimResult<()> {
+    for _ in 0..max_generations_to_run {
+      if self.in_progress.is_none() {
+        if !self.can_continue() { break; }
+        self.start_generation()?;
+      }
+
+      self.advance(usize::MAX)?;
+    }
+
+    Ok(())
+  }
+
+  // Set up the next generation, to step through with `advance`,
+  // rather than running all of it at once
+  pub fn start_generation(&mut self) -> SimResult<()> {
+    if self.in_progress.is_some() {
+      return Err(SimError::InvalidOperation("a generation is already in progress".to_string()));
+    }
+
+    if !self.can_continue() {
+      return Err(SimError::InvalidOperation("nothing is left alive to start another generation".to_string()));
+    }
+
+    self.sim.check_next_generation()?;
+
+    let mut creatures = vec![];
+    std::mem::swap(&mut creatures, &mut self.creatures);
+    self.in_progress = Some(self.sim.begin_generation(creatures));
+    Ok(())
+  }
+
+  // Run at most this many steps of the generation in progress. Once it's over, it
+  // joins the rest and its offspring wait for the next. Returns true if it's over
+  pub fn advance(&mut self, steps : usize) -> SimResult<bool> {
+    let mut generation = match self.in_progress.take() {
+      Some(g) => g,
+      None => return Ok(true),
+    };
+
+    match generation.advance(&self.sim, steps) {
+      Ok(true) => {},
+      // not over yet, or a runaway
+      not_over => {
+        self.in_progress = Some(generation);
+        return not_over;
+      },
+    }
+
+    generation.finish(&self.sim);
+    self.creatures = self.sim.end_generation(generation);
+    Ok(true)
+  }
+
+  // Act on the generation in progress, between its steps. Returns
+  // the id of the food or creature it created, if any
+  pub fn intervene(&mut self, intervention : Intervention) -> SimResult<Option<Uuid>> {
+    match self.in_progress.as_mut() {
+      Some(generation) => generation.intervene(intervention, &self.sim),
+      None => Err(SimError::InvalidOperation("there's no generation in progress to intervene in".to_string())),
+    }
+  }
+
+  // the creatures and food as they are right now
+  pub fn get_generation_in_progress(&self) -> Option<&Generation> {
+    self.in_progress.as_ref()
+  }
+
+  pub fn can_continue(&self) -> bool {
+    self.sim.generations.last().is_none_or(|g| g.has_living_creatures())
+  }
+
+  pub fn get_results(&self) -> SimulationResults {
+    SimulationResults {
+      generations: self.sim.generations.clone()
+    }
+  }
+
+  pub fn get_generation(&self, index : usize) -> SimResult<&Generation> {
+    self.sim.generations.get(index)
+      .ok_or(SimError::GenerationOutOfRange { index, count: self.sim.generations.len() })
+  }
+
+  pub fn get_statistics(&self, species_filter : Option<String>) -> SimulationStatistics {
+    get_statistics(&self.sim, species_filter)
+  }
+
+  // family tree of everything that has lived so far
+  pub fn get_genealogy(&self) -> Genealogy {
+    Genealogy::new(&self.sim.generations)
+  }
+
+  pub fn get_ancestors(&self, id : &Uuid) -> Vec<Uuid> {
+    self.get_genealogy().ancestors(id)
+  }
+
+  pub fn get_descendants(&self, id : &Uuid) -> Vec<Uuid> {
+    self.get_genealogy().descendants(id)
+  }
+
+  pub fn get_surviving_lineages(&self) -> Vec<usize> {
+    self.get_genealogy().surviving_lineages(&self.sim.generations)
+  }
+
+  pub fn get_newick(&self) -> String {
+    self.get_genealogy().to_newick()
+  }
+
+  // every invasion so far, and how its lineage has done since
+  pub fn get_invasions(&self) -> Vec<InvasionReport> {
+    let genealogy = self.get_genealogy();
+    let generations = &self.sim.generations;
+
+    generations.iter().enumerate()
+      .flat_map(|(index, generation)| generation.invasions.iter().map(move |i| (index, i)))
+      .map(|(index, invasion)| InvasionReport {
+        group: invasion.group.clone(),
+        generation: index,
+        founders: invasion.founders.len(),
+        lineage_share: genealogy.lineage_share(&invasion.founders, &generations[index..]),
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // a small world, the way a scenario gives it
+  fn config(seed : u64) -> WorldConfig {
+    serde_json::from_value(serde_json::json!({
+      "stage": { "shape": "square", "size": 300 },
+      "seed": seed,
+      "food_per_generation": [[0, 40]],
+      "preset": { "name": "default" },
+      "creatures": [{ "count": 30, "template": { "species": "default" } }],
+    })).unwrap()
+  }
+
+  // everything it gives, ids included
+  fn results(world : &World) -> String {
+    serde_json::to_string(&world.get_results()).unwrap()
+  }
+
+  fn run(cfg : &WorldConfig, generations : u32) -> String {
+    let mut world = World::from_config(cfg).unwrap();
+    world.run(generations).unwrap();
+    results(&world)
+  }
+
+  #[test]
+  fn same_seed_gives_the_same_results() {
+    let cfg = config(118);
+    assert_eq!(run(&cfg, 4), run(&cfg, 4));
+  }
+
+  #[test]
+  fn other_seeds_give_other_results() {
+    assert_ne!(run(&config(118), 4), run(&config(119), 4));
+  }
+
+  #[test]
+  fn stepping_through_gives_the_same_results_as_running() {
+    let cfg = config(118);
+    let mut world = World::from_config(&cfg).unwrap();
+    for _ in 0..4 {
+      world.start_generation().unwrap();
+      while !world.advance(1).unwrap() {}
+    }
+
+    assert_eq!(results(&world), run(&cfg, 4));
+  }
+
+  // the small world, with something changed about its stage
+  fn with_stage(stage : serde_json::Value) -> serde_json::Value {
+    let mut cfg = serde_json::to_value(config(1)).unwrap();
+    for (name, value) in stage.as_object().unwrap() {
+      cfg["stage"][name] = value.clone();
+    }
+    cfg
+  }
+
+  #[test]
+  fn bad_stages_are_config_errors() {
+    let bad = vec![
+      serde_json::json!({ "size": 0 }),
+      serde_json::json!({ "shape": "torus", "size": -1 }),
+      serde_json::json!({ "shape": "circle", "radius": -5 }),
+      serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [10, 0], [20, 0]] }),
+      serde_json::json!({ "food_placement": { "type": "gaussian_clusters", "clusters": 3, "spread": -40 } }),
+      serde_json::json!({ "food_placement": { "type": "density_map", "cell_size": 0, "cells": [[1]] } }),
+      serde_json::json!({ "food_placement": { "type": "poisson_patches", "mean_patches": 3, "radius": -10 } }),
+      serde_json::json!({ "food_dynamics": { "spawn_rate": -1 } }),
+      serde_json::json!({ "food_dynamics": { "carrion": { "energy_per_size": -5, "decay_after": 10 } } }),
+    ];
+
+    assert!(World::from_config(&serde_json::from_value(with_stage(serde_json::json!({}))).unwrap()).is_ok());
+    for stage in bad {
+      let cfg = with_stage(stage.clone());
+      let world = World::from_config(&serde_json::from_value(cfg.clone()).unwrap());
+      assert!(matches!(world, Err(SimError::InvalidConfig(_))), "{} was accepted", stage);
+      let scenario = load_scenario(&cfg.to_string(), ScenarioFormat::Json);
+      assert!(matches!(scenario, Err(SimError::InvalidConfig(_))), "{} was accepted", stage);
+    }
+  }
+
+  // through text, the way the cli and the browser keep them
+  fn resume(world : &World) -> World {
+    let text = serde_json::to_string(&world.snapshot()).unwrap();
+    World::restore(serde_json::from_str(&text).unwrap()).unwrap()
+  }
+
+  #[test]
+  fn resuming_a_snapshot_gives_the_same_results() {
+    let cfg = config(7);
+    let mut world = World::from_config(&cfg).unwrap();
+    world.run(2).unwrap();
+
+    let mut resumed = resume(&world);
+    resumed.run(3).unwrap();
+    world.run(3).unwrap();
+
+    assert_eq!(results(&resumed), results(&world));
+    assert_eq!(results(&world), run(&cfg, 5));
+  }
+
+  #[test]
+  fn resuming_part_way_through_a_generation_gives_the_same_results() {
+    let cfg = config(8);
+    let mut world = World::from_config(&cfg).unwrap();
+    world.run(1).unwrap();
+    world.start_generation().unwrap();
+    world.advance(25).unwrap();
+
+    let mut resumed = resume(&world);
+    resumed.run(3).unwrap();
+
+    assert_eq!(results(&resumed), run(&cfg, 4));
+  }
+
+  #[test]
+  fn resuming_with_a_bad_stage_is_a_config_error() {
+    let world = World::from_config(&config(9)).unwrap();
+    let mut snapshot = serde_json::to_value(world.snapshot()).unwrap();
+    snapshot["simulation"]["stage_config"]["size"] = serde_json::json!(-1);
+
+    let resumed = World::restore(serde_json::from_value(snapshot).unwrap());
+    assert!(matches!(resumed, Err(SimError::InvalidConfig(_))));
+  }
+}
@@ -0,0 +1,174 @@
+// Headless runner for the simulation core.
+//
+// Usage: simulate <scenario.json|toml> [--generations N] [--out DIR] [--step K]
+//        simulate <scenario.json|toml> --convert <scenario.json|toml>
+//        simulate --resume <snapshot.json> [--generations N] [--out DIR] [--step K]
+//
+// With `--step` each generation is stepped through K steps at a time,
+// the way a live view would, which gives the same results.
+// `--convert` writes the scenario in the current version (and either format) instead of running it.
+//
+// Build natively with `cargo run --no-default-features --bin simulate -- ...`
+extern crate app;
+extern crate serde_json;
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process;
+use std::path::Path;
+use app::{validate_config, default_max_generations, read_scenario, save_scenario};
+use app::{ScenarioFormat, World, WorldSnapshot, WorldConfig};
+
+struct Args {
+  scenario : Option<PathBuf>,
+  resume : Option<PathBuf>,
+  generations : Option<u32>,
+  out : PathBuf,
+  step : Option<usize>,
+  convert : Option<PathBuf>,
+}
+
+fn usage() -> ! {
+  eprintln!("usage: simulate <scenario.json|toml> [--generations N] [--out DIR] [--step K]");
+  eprintln!("       simulate <scenario.json|toml> --convert <scenario.json|toml>");
+  eprintln!("       simulate --resume <snapshot.json> [--generations N] [--out DIR] [--step K]");
+  process::exit(2);
+}
+
+fn parse_args() -> Args {
+  let mut scenario = None;
+  let mut resume = None;
+  let mut generations = None;
+  let mut out = PathBuf::from(".");
+  let mut step = None;
+  let mut convert = None;
+  let mut args = std::env::args().skip(1);
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--generations" | "-n" => {
+        generations = args.next().and_then(|n| n.parse().ok());
+        if generations.is_none() { usage() }
+      },
+      "--out" | "-o" => {
+        out = args.next().map(PathBuf::from).unwrap_or_else(|| usage());
+      },
+      "--resume" | "-r" => {
+        resume = args.next().map(PathBuf::from);
+        if resume.is_none() { usage() }
+      },
+      "--step" | "-s" => {
+        step = args.next().and_then(|k| k.parse().ok()).filter(|k| *k > 0);
+        if step.is_none() { usage() }
+      },
+      "--convert" | "-c" => {
+        convert = args.next().map(PathBuf::from);
+        if convert.is_none() { usage() }
+      },
+      "--help" | "-h" => usage(),
+      _ if scenario.is_none() => scenario = Some(PathBuf::from(arg)),
+      _ => usage(),
+    }
+  }
+
+  // exactly one of the two
+  if scenario.is_some() == resume.is_some() { usage() }
+  if convert.is_some() && scenario.is_none() { usage() }
+
+  Args {
+    scenario,
+    resume,
+    generations,
+    out,
+    step,
+    convert,
+  }
+}
+
+fn write_json<T : serde::Serialize>(path : PathBuf, value : &T) -> Result<(), String> {
+  let file = File::create(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+  serde_json::to_writer(BufWriter::new(file), value).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn read_json<T : serde::de::DeserializeOwned>(path : &PathBuf) -> Result<T, String> {
+  let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+  serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// by its extension, json unless it's `.toml`
+fn scenario_format(path : &Path) -> ScenarioFormat {
+  path.extension()
+    .and_then(|ext| ext.to_str())
+    .and_then(ScenarioFormat::from_name)
+    .unwrap_or(ScenarioFormat::Json)
+}
+
+// a scenario of any version, in either format
+fn read_scenario_file(path : &PathBuf) -> Result<WorldConfig, String> {
+  let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+  let cfg = read_scenario(&text, scenario_format(path)).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+  // every problem at once, rather than just the first
+  let problems = validate_config(&cfg);
+  if !problems.is_empty() {
+    let lines : Vec<String> = problems.iter().map(|d| format!("  {}", d)).collect();
+    return Err(format!("{}:\n{}", path.display(), lines.join("\n")));
+  }
+
+  serde_json::from_value(cfg).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn run(args : Args) -> Result<(), String> {
+  if let (Some(path), Some(out)) = (&args.scenario, &args.convert) {
+    let scenario = read_scenario_file(path)?;
+    let text = save_scenario(&scenario, scenario_format(out))?;
+    fs::write(out, text).map_err(|e| format!("{}: {}", out.display(), e))?;
+
+    eprintln!("wrote {}", out.display());
+    return Ok(());
+  }
+
+  let (mut world, max_generations) = match (&args.scenario, &args.resume) {
+    (Some(path), _) => {
+      let scenario = read_scenario_file(path)?;
+      (World::from_config(&scenario)?, scenario.max_generations)
+    },
+    (None, Some(path)) => {
+      let snapshot : WorldSnapshot = read_json(path)?;
+      (World::restore(snapshot)?, default_max_generations())
+    },
+    _ => unreachable!(),
+  };
+
+  let generations = args.generations.unwrap_or(max_generations);
+  match args.step {
+    Some(k) => {
+      for _ in 0..generations {
+        if !world.can_continue() { break; }
+        world.start_generation()?;
+        while !world.advance(k)? {}
+      }
+    },
+    None => world.run(generations)?,
+  }
+
+  fs::create_dir_all(&args.out).map_err(|e| format!("{}: {}", args.out.display(), e))?;
+  write_json(args.out.join("results.json"), &world.get_results())?;
+  write_json(args.out.join("statistics.json"), &world.get_statistics(None))?;
+  write_json(args.out.join("snapshot.json"), &world.snapshot())?;
+  write_json(args.out.join("lineages.json"), &world.get_surviving_lineages())?;
+  write_json(args.out.join("invasions.json"), &world.get_invasions())?;
+  let genealogy = args.out.join("genealogy.nwk");
+  fs::write(&genealogy, world.get_newick()).map_err(|e| format!("{}: {}", genealogy.display(), e))?;
+
+  eprintln!("ran {} generations", world.sim.generations.len());
+  Ok(())
+}
+
+fn main() {
+  if let Err(e) = run(parse_args()) {
+    eprintln!("error: {}", e);
+    process::exit(1);
+  }
+}
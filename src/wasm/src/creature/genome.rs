@@ -0,0 +1,294 @@
+use rand::Rng;
+use rand::distributions::{Normal, LogNormal, Cauchy, StandardNormal, Distribution};
+use std::cell::{RefMut};
+use std::collections::{BTreeMap, BTreeSet};
+use crate::simulation::{SimRng, Diagnostic};
+
+// lower bounds of the traits the simulation itself relies on,
+// used unless the config declares its own
+const BUILTIN_TRAITS : [(&str, f64); 6] = [
+  ("speed", f64::MIN_POSITIVE), // how far can it move in one step?
+  ("size", f64::MIN_POSITIVE),
+  ("sense_range", 0.), // how far can it see?
+  ("reach", f64::MIN_POSITIVE), // how far can it interact with something?
+  ("flee_distance", 0.),
+  ("life_span", f64::MIN_POSITIVE),
+];
+
+// what a trait is worth if the creature doesn't have it
+const MISSING_TRAIT_VALUE : f64 = 1.0;
+
+fn builtin_min(name : &str) -> Option<f64> {
+  BUILTIN_TRAITS.iter().find(|(n, _)| *n == name).map(|(_, min)| *min)
+}
+
+// how an offspring inherits a trait from two parents
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recombination {
+  // halfway between the parents
+  Blend,
+  // one parent's value or the other's, with equal chance
+  Pick,
+}
+
+// keep a value within bounds by bouncing it off them, rather than clamping,
+// so that mutations don't pile up on the bounds
+fn reflect(v : f64, min : f64, max : f64) -> f64 {
+  if v >= min && v <= max {
+    return v;
+  }
+
+  if !v.is_finite() {
+    return v.max(min).min(max);
+  }
+
+  match (min.is_finite(), max.is_finite()) {
+    (true, true) => {
+      let width = max - min;
+      if width <= 0. { return min; }
+      let t = (v - min).rem_euclid(2. * width);
+      min + if t > width { 2. * width - t } else { t }
+    },
+    (true, false) => 2. * min - v,
+    (false, true) => 2. * max - v,
+    _ => v,
+  }
+}
+
+// How a trait mutates from parent to child. The gene's `variance`
+// is the spread of whichever distribution is used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mutation {
+  // spread is the standard deviation (how it has always behaved)
+  #[default]
+  GaussianStdDev,
+  // spread is the variance
+  GaussianVariance,
+  // multiplied by e^N(0, spread). Never changes sign
+  LogNormal,
+  // heavy tailed, with spread as the scale
+  Cauchy,
+  // anywhere within spread either side
+  Uniform,
+  // only mutates with the given probability, otherwise copied as is
+  Occasionally { probability : f64, distribution : Box<Mutation> },
+}
+
+impl Mutation {
+  fn is_default(&self) -> bool { *self == Mutation::default() }
+
+  fn sample(&self, value : f64, spread : f64, rng : &mut RefMut<SimRng>) -> f64 {
+    match self {
+      Mutation::GaussianStdDev => Normal::new(value, spread).sample(&mut **rng),
+      Mutation::GaussianVariance => Normal::new(value, spread.sqrt()).sample(&mut **rng),
+      Mutation::LogNormal => value * LogNormal::new(0., spread).sample(&mut **rng),
+      Mutation::Cauchy if spread > 0. => Cauchy::new(value, spread).sample(&mut **rng),
+      Mutation::Uniform if spread > 0. => rng.gen_range(value - spread, value + spread),
+      Mutation::Cauchy | Mutation::Uniform => value,
+      Mutation::Occasionally { probability, distribution } => {
+        if rng.gen::<f64>() < *probability {
+          distribution.sample(value, spread, rng)
+        } else {
+          value
+        }
+      },
+    }
+  }
+}
+
+// A single mutatable trait. In config either `[value, variance]`
+// or `{ value, variance, min, max, mutation, self_adaptation }` for the extras.
+//
+// With `self_adaptation` (a learning rate, often around 1/sqrt(number of traits))
+// the variance is inherited and mutated too, log-normally as in
+// evolution strategies, so that mutation rates can evolve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "GeneConfig", into = "GeneConfig")]
+pub struct Gene {
+  pub value : f64,
+  pub variance : f64,
+  pub min : Option<f64>,
+  pub max : Option<f64>,
+  pub mutation : Mutation,
+  pub self_adaptation : Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GeneConfig {
+  Simple(f64, f64),
+  Full {
+    value : f64,
+    variance : f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min : Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max : Option<f64>,
+    #[serde(default, skip_serializing_if = "Mutation::is_default")]
+    mutation : Mutation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    self_adaptation : Option<f64>,
+  },
+}
+
+impl From<GeneConfig> for Gene {
+  fn from(cfg : GeneConfig) -> Self {
+    match cfg {
+      GeneConfig::Simple(value, variance) => Gene::new(value, variance),
+      GeneConfig::Full { value, variance, min, max, mutation, self_adaptation } =>
+        Gene { value, variance, min, max, mutation, self_adaptation },
+    }
+  }
+}
+
+impl From<Gene> for GeneConfig {
+  fn from(gene : Gene) -> Self {
+    match gene {
+      Gene { value, variance, min: None, max: None, ref mutation, self_adaptation: None }
+        if mutation.is_default() => GeneConfig::Simple(value, variance),
+      Gene { value, variance, min, max, mutation, self_adaptation } =>
+        GeneConfig::Full { value, variance, min, max, mutation, self_adaptation },
+    }
+  }
+}
+
+impl Gene {
+  pub fn new(value : f64, variance : f64) -> Self {
+    Gene { value, variance, min: None, max: None, mutation: Mutation::default(), self_adaptation: None }
+  }
+
+  // anything wrong with it, by field. The value itself is at the trait's own path
+  pub fn problems(&self, name : &str) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+    let (min, max) = self.bounds(name);
+    let finite = |field : &str, v : Option<f64>, problems : &mut Vec<Diagnostic>| {
+      if let Some(v) = v.filter(|v| !v.is_finite()) {
+        problems.push(Diagnostic::new(field, format!("must be a number, not {}", v)));
+      }
+    };
+
+    finite("", Some(self.value), &mut problems);
+    finite("variance", Some(self.variance), &mut problems);
+    finite("min", self.min, &mut problems);
+    finite("max", self.max, &mut problems);
+    if !problems.is_empty() {
+      return problems;
+    }
+
+    if min > max {
+      problems.push(Diagnostic::new("min", format!("must not be more than max ({} > {})", min, max)));
+    } else if self.value < min {
+      let builtin = self.min.is_none() && builtin_min(name) == Some(f64::MIN_POSITIVE);
+      let message = if builtin { "must be more than 0".to_string() } else { format!("must be at least {}", min) };
+      problems.push(Diagnostic::new("", format!("{}, not {}", message, self.value)));
+    } else if self.value > max {
+      problems.push(Diagnostic::new("", format!("must be at most {}, not {}", max, self.value)));
+    }
+
+    if self.variance < 0. {
+      problems.push(Diagnostic::new("variance", format!("must not be negative, not {}", self.variance)));
+    }
+
+    if let Some(rate) = self.self_adaptation.filter(|r| !(r.is_finite() && *r >= 0.)) {
+      problems.push(Diagnostic::new("self_adaptation", format!("must be zero or more, not {}", rate)));
+    }
+
+    let mut mutation = &self.mutation;
+    let mut path = "mutation".to_string();
+    while let Mutation::Occasionally { probability, distribution } = mutation {
+      if !(*probability >= 0. && *probability <= 1.) {
+        problems.push(Diagnostic::new(format!("{}.probability", path), format!("must be between 0 and 1, not {}", probability)));
+      }
+      mutation = distribution;
+      path = format!("{}.distribution", path);
+    }
+
+    problems
+  }
+
+  fn bounds(&self, name : &str) -> (f64, f64) {
+    (
+      self.min.or_else(|| builtin_min(name)).unwrap_or(f64::NEG_INFINITY),
+      self.max.unwrap_or(f64::INFINITY),
+    )
+  }
+
+  fn mutated(&self, name : &str, rng : &mut RefMut<SimRng>) -> Self {
+    // the variance first, so the value mutates by the child's own variance
+    let variance = match self.self_adaptation {
+      Some(rate) => self.variance * (rate * StandardNormal.sample(&mut **rng)).exp(),
+      None => self.variance,
+    };
+
+    let (min, max) = self.bounds(name);
+    let value = reflect(self.mutation.sample(self.value, variance, rng), min, max);
+
+    Gene { value, variance, ..self.clone() }
+  }
+
+  fn recombined(&self, other : &Gene, how : Recombination, rng : &mut RefMut<SimRng>) -> Self {
+    match how {
+      Recombination::Blend => Gene {
+        value: 0.5 * (self.value + other.value),
+        variance: 0.5 * (self.variance + other.variance),
+        ..self.clone()
+      },
+      Recombination::Pick => if rng.gen::<bool>() { self.clone() } else { other.clone() },
+    }
+  }
+}
+
+// Every trait of a creature, by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Genome(BTreeMap<String, Gene>);
+
+impl Genome {
+  // the builtin traits, all at the same value and variance
+  pub fn uniform(value : f64, variance : f64) -> Self {
+    Genome(BUILTIN_TRAITS.iter().map(|(name, _)| (name.to_string(), Gene::new(value, variance))).collect())
+  }
+
+  pub fn get(&self, name : &str) -> Option<&Gene> {
+    self.0.get(name)
+  }
+
+  pub fn value(&self, name : &str) -> f64 {
+    self.0.get(name).map_or(MISSING_TRAIT_VALUE, |g| g.value)
+  }
+
+  pub fn set(&mut self, name : &str, gene : Gene) {
+    self.0.insert(name.to_string(), gene);
+  }
+
+  pub fn set_value(&mut self, name : &str, value : f64) {
+    self.0.entry(name.to_string())
+      .or_insert_with(|| Gene::new(value, 0.))
+      .value = value;
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&String, &Gene)> {
+    self.0.iter()
+  }
+
+  pub fn mutated(&self, rng : &mut RefMut<SimRng>) -> Self {
+    Genome(self.0.iter().map(|(name, g)| (name.clone(), g.mutated(name, rng))).collect())
+  }
+
+  // traits only one parent has are passed on as they are
+  pub fn recombined(&self, other : &Genome, how : Recombination, rng : &mut RefMut<SimRng>) -> Self {
+    let names : BTreeSet<&String> = self.0.keys().chain(other.0.keys()).collect();
+
+    Genome(names.into_iter().map(|name| {
+      let gene = match (self.0.get(name), other.0.get(name)) {
+        (Some(a), Some(b)) => a.recombined(b, how, rng),
+        (Some(a), None) => a.clone(),
+        (None, Some(b)) => b.clone(),
+        (None, None) => unreachable!(),
+      };
+      (name.clone(), gene)
+    }).collect())
+  }
+}
@@ -1,20 +1,16 @@
-use crate::simulation::{Edible, FoodType};
-use crate::simulation::Step;
+use crate::simulation::{Edible, FoodType, EnergyConfig};
+use crate::simulation::{Step, SimRng};
 use crate::math::*;
 use crate::na::{Point2, Unit, Vector2};
-use std::collections::HashMap;
 use std::cell::{RefMut};
-use rand::{rngs::SmallRng};
 use uuid::Uuid;
 
 const ENERGY_COST_SCALE_FACTOR : f64 = 1. / 10_000.;
 
-fn get_uuid() -> Uuid {
-  Uuid::new_v4()
-}
-
-mod mutatable;
-use mutatable::*;
+mod genome;
+pub use genome::*;
+mod template;
+pub use template::*;
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 enum CreatureState {
@@ -53,67 +49,78 @@ pub struct Objective {
 pub struct Creature {
   pub id : Uuid,
   pub species : String,
-  // mutatable
-  speed : PositiveNonZeroMutatable<f64>, // how far can it move in one step?
-  size : PositiveNonZeroMutatable<f64>,
-  sense_range : PositiveMutatable<f64>, // how far can it see?
-  reach : PositiveNonZeroMutatable<f64>, // how far can it interact with something?
-  flee_distance : PositiveMutatable<f64>,
-  life_span: PositiveNonZeroMutatable<f64>,
+  // mutatable traits, each its own field when serialized
+  #[serde(flatten)]
+  genome : Genome,
+
+  // who it came from. None for the first generation
+  #[serde(default)]
+  pub parents : Vec<Uuid>,
 
   // other
   pub foods_eaten : Vec<(Step, Uuid, FoodType)>,
+  // total worth of everything eaten this generation
+  #[serde(default)]
+  pub energy_eaten : f64,
   pub energy : f64,
   pub energy_consumed: f64,
   pub age : u32,
   pub pos : Point2<f64>,
   pub home_pos : Point2<f64>,
+  // home was put somewhere by hand, so home behaviours leave it there
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub home_fixed : bool,
 
   // array of position vectors
   pub movement_history : Vec<Point2<f64>>,
   pub status_history : Vec<String>,
 
   state : CreatureState,
-  objective: Option<Objective>
+  objective: Option<Objective>,
+
+  // where it was last step, if the stage moved it somewhere else
+  // since (eg: wrapped it around a torus)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  unwrapped_last_position: Option<Point2<f64>>,
 }
 
 impl Edible for Creature {
   fn get_edible_id(&self) -> Uuid { self.id }
   fn get_type(&self) -> FoodType { "creature".into() }
+  fn get_energy(&self, cfg : &EnergyConfig) -> f64 { self.get_size() * cfg.prey_energy_per_size }
 }
 
 impl Creature {
-  pub fn default( pos : &Point2<f64> ) -> Self {
+  pub fn default( id : Uuid, pos : &Point2<f64> ) -> Self {
     Creature {
-      id: get_uuid(),
+      id,
       species: "default".to_string(),
       state: CreatureState::ACTIVE,
-      speed: PositiveNonZeroMutatable(1.0, 1.),
-      size: PositiveNonZeroMutatable(1.0, 1.),
-      sense_range: PositiveMutatable(1.0, 1.0),
-      reach: PositiveNonZeroMutatable(1.0, 1.0),
-      flee_distance: PositiveMutatable(1.0, 1.0),
-      life_span: PositiveNonZeroMutatable(1.0, 1.0),
+      genome: Genome::uniform(1.0, 1.0),
       energy: 500.0,
 
+      parents: vec![],
       foods_eaten: vec![],
+      energy_eaten: 0.0,
       energy_consumed: 0.0,
 
       age: 0,
 
       pos: pos.clone(),
       home_pos: pos.clone(),
+      home_fixed: false,
       movement_history: vec![pos.clone()],
       status_history: vec![],
       objective: None,
+      unwrapped_last_position: None,
     }
   }
 
   // Instance methods
   //------------------
-  pub fn with_new_position(&self, pos : &Point2<f64>) -> Self {
+  pub fn with_new_position(&self, id : Uuid, pos : &Point2<f64>) -> Self {
     let mut ret = self.clone();
-    ret.id = get_uuid();
+    ret.id = id;
     ret.pos = pos.clone();
     ret.home_pos = pos.clone();
     ret.movement_history = vec![pos.clone()];
@@ -122,71 +129,57 @@ impl Creature {
   }
 
   // mutate the creature properties and return a new instance
-  pub fn mutate(&self, rng : &mut RefMut<SmallRng>) -> Self {
+  pub fn mutate(&self, id : Uuid, rng : &mut RefMut<SimRng>) -> Self {
     Creature {
-      speed: self.speed.get_mutated(rng),
-      size: self.size.get_mutated(rng),
-      sense_range: self.sense_range.get_mutated(rng),
-      reach: self.reach.get_mutated(rng),
-      flee_distance: self.flee_distance.get_mutated(rng),
-      life_span: self.life_span.get_mutated(rng),
+      genome: self.genome.mutated(rng),
       energy: self.energy,
       species: self.species.clone(),
+      parents: vec![self.id],
+
+      ..Creature::default(id, &self.home_pos)
+    }
+  }
+
+  // offspring of this creature and a mate, with each trait
+  // inherited from both of them, then mutated
+  pub fn mate(&self, other : &Creature, how : Recombination, id : Uuid, rng : &mut RefMut<SimRng>) -> Self {
+    let combined = Creature {
+      genome: self.genome.recombined(&other.genome, how, rng),
+      ..self.clone()
+    };
 
-      ..Creature::default(&self.home_pos)
+    Creature {
+      parents: vec![self.id, other.id],
+      ..combined.mutate(id, rng)
     }
   }
 
   // copy self, but increase age.
   pub fn grow_older(&self) -> Self {
-    let Creature {
-      id,
-      speed,
-      size,
-      sense_range,
-      reach,
-      flee_distance,
-      life_span,
-      energy,
-      ..
-    } = *self;
-
     Creature {
-      id,
-      speed,
-      size,
-      sense_range,
-      reach,
-      flee_distance,
-      life_span,
-      energy,
+      genome: self.genome.clone(),
+      energy: self.energy,
       age: self.age + 1,
       species: self.species.clone(),
+      parents: self.parents.clone(),
 
-      ..Creature::default(&self.home_pos)
+      ..Creature::default(self.id, &self.home_pos)
     }
   }
 
-  pub fn get_size(&self) -> f64 { self.size.0 }
-  pub fn get_speed(&self) -> f64 { self.speed.0 * self.size.0 / 10. }
-  pub fn set_speed(&mut self, speed : f64) { self.speed.0 = speed }
-  pub fn get_sense_range(&self) -> f64 { self.sense_range.0 }
-  pub fn set_sense_range(&mut self, sense : f64) { self.sense_range.0 = sense }
+  pub fn get_size(&self) -> f64 { self.genome.value("size") }
+  pub fn get_speed(&self) -> f64 { self.genome.value("speed") * self.get_size() / 10. }
+  pub fn set_speed(&mut self, speed : f64) { self.genome.set_value("speed", speed) }
+  pub fn get_sense_range(&self) -> f64 { self.genome.value("sense_range") }
+  pub fn set_sense_range(&mut self, sense : f64) { self.genome.set_value("sense_range", sense) }
   // Reach is at least one quarter of the blob's size (which means they can reach what
   // they touch with their bodies)
-  pub fn get_reach(&self) -> f64 { self.reach.0.max(self.get_size() / 4.) }
-  pub fn get_life_span(&self) -> f64 { self.life_span.0 }
-
-  pub fn get_traits(&self) -> HashMap<&'static str, (f64, f64)> {
-    let mut traits = HashMap::new();
-    traits.insert("size", self.size.into());
-    traits.insert("speed", self.speed.into());
-    traits.insert("sense_range", self.sense_range.into());
-    traits.insert("reach", self.reach.into());
-    traits.insert("life_span", self.life_span.into());
-    traits.insert("flee_distance", self.flee_distance.into());
-    traits
-  }
+  pub fn get_reach(&self) -> f64 { self.genome.value("reach").max(self.get_size() / 4.) }
+  pub fn get_life_span(&self) -> f64 { self.genome.value("life_span") }
+  pub fn get_flee_distance(&self) -> f64 { self.genome.value("flee_distance") }
+
+  pub fn get_genome(&self) -> &Genome { &self.genome }
+  pub fn set_trait(&mut self, name : &str, value : f64) { self.genome.set_value(name, value) }
 
   pub fn is_alive(&self) -> bool {
     match self.state {
@@ -205,6 +198,7 @@ impl Creature {
   // move the creature, record its motion in history,
   // apply an energy cost.
   pub fn move_to( &mut self, pos : Point2<f64> ){
+    self.unwrapped_last_position = None;
     self.pos = pos.clone();
     self.movement_history.push(pos);
     let cost = self.get_motion_energy_cost();
@@ -228,12 +222,12 @@ impl Creature {
         ObjectiveIntensity::VitalAversion => -1. * d, // other way
         _ => d,
       }
-    }).filter(|d| d.norm() != 0.).unwrap_or_else(|| {
+    }).filter(|d| d.norm() > 0.).or_else(|| {
       // or the direction it was traveling before
       self.get_last_position()
         .map(|last| self.pos - last)
-        .unwrap_or_else(|| Vector2::x()) // or the x axis
-    });
+        .filter(|d| d.norm() > 0.)
+    }).unwrap_or_else(Vector2::x); // or the x axis
 
     Unit::new_normalize(disp)
   }
@@ -269,8 +263,10 @@ impl Creature {
     self.objective = None;
   }
 
-  pub fn eat_food(&mut self, step: Step, food: &dyn Edible){
-    self.foods_eaten.push((step, food.get_edible_id(), food.get_type()))
+  // what it eats goes towards its energy left
+  pub fn eat_food(&mut self, step: Step, food: &dyn Edible, energy: f64){
+    self.foods_eaten.push((step, food.get_edible_id(), food.get_type()));
+    self.energy_eaten += energy;
   }
 
   pub fn sleep(&mut self){
@@ -290,7 +286,13 @@ impl Creature {
     let len = self.movement_history.len();
     if len <= 1 { return None; }
 
-    Some(self.movement_history[len - 2])
+    self.unwrapped_last_position.or(Some(self.movement_history[len - 2]))
+  }
+
+  // record where it came from, relative to where it is now,
+  // when that isn't simply the previous position in its history
+  pub fn set_unwrapped_last_position( &mut self, pos : Point2<f64> ){
+    self.unwrapped_last_position = Some(pos);
   }
 
   pub fn can_see(&self, pt : &Point2<f64>) -> bool {
@@ -311,7 +313,7 @@ impl Creature {
   pub fn within_flee_distance(&self, pt : &Point2<f64>) -> bool {
     if self.can_see(pt) {
       let d = (self.get_position() - pt).norm();
-      d < self.flee_distance.0
+      d < self.get_flee_distance()
     } else {
       false
     }
@@ -321,8 +323,13 @@ impl Creature {
     (pt - self.pos).norm() <= self.get_reach()
   }
 
+  // what it started with, plus what it ate, less what it used
+  pub fn get_net_energy(&self) -> f64 {
+    self.energy + self.energy_eaten - self.energy_consumed
+  }
+
   pub fn get_energy_left(&self) -> f64 {
-    (self.energy - self.energy_consumed).max(0.)
+    self.get_net_energy().max(0.)
   }
 
   pub fn apply_energy_cost( &mut self, cost : f64 ){
@@ -333,3 +340,25 @@ impl Creature {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn direction_is_never_a_zero_vector() {
+    let pos = Point2::new(100., 100.);
+    let mut creature = Creature::default(Uuid::nil(), &pos);
+    // stayed put, and wants to get away from right where it is
+    creature.move_to(pos);
+    creature.add_objective(Objective {
+      pos,
+      intensity: ObjectiveIntensity::MinorAversion,
+      reason: String::from("avoiding rival"),
+    });
+
+    let dir = creature.get_direction();
+    assert!(dir.x.is_finite() && dir.y.is_finite());
+    assert_eq!(dir.into_inner(), Vector2::x());
+  }
+}
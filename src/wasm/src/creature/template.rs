@@ -0,0 +1,248 @@
+use serde::{Serialize, Serializer, Deserialize, Deserializer, de::Error};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::simulation::Diagnostic;
+use super::*;
+
+// traits a template gets if it doesn't give them, as [value, variance]
+const DEFAULT_TRAITS : [(&str, f64, f64); 6] = [
+  ("speed", 10., 0.5),
+  ("size", 10., 0.5),
+  ("sense_range", 20., 0.5),
+  ("reach", 1., 0.),
+  // for now flee within sight range
+  ("flee_distance", 1e12, 0.),
+  ("life_span", 1e4, 0.),
+];
+
+const DEFAULT_SPECIES : &str = "default";
+const DEFAULT_ENERGY : f64 = 500.;
+
+// Templates used to be whole creatures, so these are still
+// accepted but ignored. They're set when a creature is made from it
+pub const CREATURE_STATE_FIELDS : [&str; 14] = [
+  "id",
+  "state",
+  "parents",
+  "foods_eaten",
+  "energy_eaten",
+  "energy_consumed",
+  "age",
+  "pos",
+  "home_pos",
+  "home_fixed",
+  "movement_history",
+  "status_history",
+  "objective",
+  "unwrapped_last_position",
+];
+
+// What new creatures are made from. The usual traits can be given by name, anything
+// else goes in `traits`, so a misspelt one isn't taken for a new trait, eg:
+// `{ "species": "fox", "energy": 600, "speed": [12, 0.5], "traits": { "stripes": [3, 1] } }`
+// The usual traits take their defaults if not given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatureTemplate {
+  pub species : String,
+  pub energy : f64,
+  pub traits : Genome,
+}
+
+impl Default for CreatureTemplate {
+  fn default() -> Self {
+    let mut traits = Genome::default();
+    for (name, value, variance) in DEFAULT_TRAITS.iter() {
+      traits.set(name, Gene::new(*value, *variance));
+    }
+
+    CreatureTemplate {
+      species: DEFAULT_SPECIES.to_string(),
+      energy: DEFAULT_ENERGY,
+      traits,
+    }
+  }
+}
+
+pub fn is_usual_trait(name : &str) -> bool {
+  DEFAULT_TRAITS.iter().any(|(n, _, _)| *n == name)
+}
+
+// names a creature already uses for something else
+fn is_reserved(name : &str) -> bool {
+  name == "species" || name == "energy" || CREATURE_STATE_FIELDS.contains(&name)
+}
+
+fn reserved(name : &str) -> Diagnostic {
+  Diagnostic::new(format!("traits.{}", name), "is a creature field, so it can't be a trait")
+}
+
+fn gene(value : &Value, path : String) -> Result<Gene, Diagnostic> {
+  serde_json::from_value(value.clone()).map_err(|_| Diagnostic::new(path,
+    "must be [value, variance] or { value, variance, min, max, mutation, self_adaptation }"))
+}
+
+fn number(value : &Value) -> Option<f64> {
+  value.as_f64().filter(|v| v.is_finite())
+}
+
+impl CreatureTemplate {
+  // Read one from its config, or every problem reading it
+  pub fn from_value(cfg : &Value) -> Result<Self, Vec<Diagnostic>> {
+    let (template, problems) = CreatureTemplate::parse(cfg);
+    if problems.is_empty() {
+      Ok(template)
+    } else {
+      Err(problems)
+    }
+  }
+
+  // As much of it as could be read (with defaults for the rest), and the problems with the rest
+  pub fn parse(cfg : &Value) -> (Self, Vec<Diagnostic>) {
+    let fields = match cfg.as_object() {
+      Some(fields) => fields,
+      None => return (
+        CreatureTemplate::default(),
+        vec![Diagnostic::new("", "must be an object of traits, eg: `{ \"speed\": [10, 0.5] }`")],
+      ),
+    };
+
+    let mut template = CreatureTemplate::default();
+    let mut problems = vec![];
+
+    for (name, value) in fields {
+      match name.as_str() {
+        "species" => match value.as_str() {
+          Some(species) => template.species = species.to_string(),
+          None => problems.push(Diagnostic::new(name.as_str(), "must be a string")),
+        },
+        "energy" => match number(value) {
+          Some(energy) => template.energy = energy,
+          None => problems.push(Diagnostic::new(name.as_str(), "must be a number")),
+        },
+        "traits" => match value.as_object() {
+          Some(traits) => for (name, value) in traits {
+            if is_reserved(name) {
+              problems.push(reserved(name));
+              continue;
+            }
+
+            match gene(value, format!("traits.{}", name)) {
+              Ok(gene) => template.traits.set(name, gene),
+              Err(problem) => problems.push(problem),
+            }
+          },
+          None => problems.push(Diagnostic::new(name.as_str(), "must be an object of traits, by name")),
+        },
+        _ if CREATURE_STATE_FIELDS.contains(&name.as_str()) => {},
+        _ if is_usual_trait(name) => match gene(value, name.clone()) {
+          Ok(gene) => template.traits.set(name, gene),
+          Err(problem) => problems.push(problem),
+        },
+        _ => {
+          let usual : Vec<&str> = DEFAULT_TRAITS.iter().map(|(n, _, _)| *n).collect();
+          let message = format!("unknown field, expected species, energy, traits or one of: {}", usual.join(", "));
+          problems.push(Diagnostic::new(name.as_str(), message));
+        },
+      }
+    }
+
+    (template, problems)
+  }
+
+  // anything out of range, by field
+  pub fn problems(&self) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+
+    if self.species.is_empty() {
+      problems.push(Diagnostic::new("species", "must not be empty"));
+    }
+
+    if self.energy.is_nan() || self.energy < 0. {
+      problems.push(Diagnostic::new("energy", format!("must not be negative, not {}", self.energy)));
+    }
+
+    for (name, gene) in self.traits.iter() {
+      if is_reserved(name) {
+        problems.push(reserved(name));
+        continue;
+      }
+
+      let path = if is_usual_trait(name) { name.clone() } else { format!("traits.{}", name) };
+      problems.extend(gene.problems(name).into_iter().map(|d| d.within(&path)));
+    }
+
+    problems
+  }
+
+  // a new creature made from it
+  pub fn build(&self, id : Uuid, pos : &Point2<f64>) -> Creature {
+    Creature {
+      species: self.species.clone(),
+      genome: self.traits.clone(),
+      energy: self.energy,
+
+      ..Creature::default(id, pos)
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct TemplateFields<'a> {
+  species : &'a str,
+  energy : f64,
+  #[serde(flatten)]
+  usual : BTreeMap<&'a str, &'a Gene>,
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  traits : BTreeMap<&'a str, &'a Gene>,
+}
+
+impl Serialize for CreatureTemplate {
+  fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+    let (usual, traits) = self.traits.iter()
+      .map(|(name, gene)| (name.as_str(), gene))
+      .partition(|(name, _)| is_usual_trait(name));
+    TemplateFields { species: &self.species, energy: self.energy, usual, traits }.serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for CreatureTemplate {
+  fn deserialize<D : Deserializer<'de>>(deserializer : D) -> Result<Self, D::Error> {
+    let cfg = Value::deserialize(deserializer)?;
+    CreatureTemplate::from_value(&cfg).map_err(|problems| D::Error::custom(Diagnostic::join(&problems)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn paths(cfg : Value) -> Vec<String> {
+    CreatureTemplate::from_value(&cfg).err().unwrap_or_default().into_iter().map(|d| d.path).collect()
+  }
+
+  #[test]
+  fn misspelt_traits_are_not_new_traits() {
+    assert_eq!(paths(serde_json::json!({ "species": "fox", "sped": [12, 0.5] })), vec!["sped"]);
+  }
+
+  #[test]
+  fn other_traits_go_in_traits() {
+    let cfg = serde_json::json!({ "species": "fox", "speed": [12, 0.5], "traits": { "stripes": [3, 1] } });
+    let template = CreatureTemplate::from_value(&cfg).unwrap();
+    assert_eq!(template.traits.get("stripes"), Some(&Gene::new(3., 1.)));
+    assert_eq!(template.traits.get("speed"), Some(&Gene::new(12., 0.5)));
+
+    assert_eq!(paths(serde_json::json!({ "traits": { "stripes": "lots" } })), vec!["traits.stripes"]);
+    assert_eq!(paths(serde_json::json!({ "traits": [3, 1] })), vec!["traits"]);
+    assert_eq!(paths(serde_json::json!({ "traits": { "energy": [3, 1], "age": [2, 0] } })), vec!["traits.age", "traits.energy"]);
+  }
+
+  #[test]
+  fn reads_back_what_it_writes() {
+    let cfg = serde_json::json!({ "species": "fox", "traits": { "stripes": [3, 1] } });
+    let template = CreatureTemplate::from_value(&cfg).unwrap();
+    let written = serde_json::to_value(&template).unwrap();
+    assert_eq!(written["traits"]["stripes"], serde_json::json!([3., 1.]));
+    assert_eq!(CreatureTemplate::from_value(&written).unwrap(), template);
+  }
+}
@@ -1,8 +1,10 @@
+#[cfg(feature = "wasm")]
 extern crate wasm_bindgen;
 #[allow(unused_imports)]
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -11,6 +13,7 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC : wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn browser_debug() {
   // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -29,6 +32,7 @@ pub mod stage;
 pub mod creature;
 pub mod simulation;
 
+#[cfg(feature = "wasm")]
 mod timer;
 
 mod math;
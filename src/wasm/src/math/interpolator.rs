@@ -1,19 +1,41 @@
 use super::lerp;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Interpolator {
   pts: Vec<(f64, f64)>
 }
 
 impl Interpolator {
-  pub fn new(pts : &Vec<(f64, f64)>) -> Self {
+  // points must be in order of x, with at least one of them
+  pub fn new(pts : &[(f64, f64)]) -> Result<Self, String> {
+    if pts.is_empty() {
+      return Err("needs at least one point".to_string());
+    }
+
+    if let Some(p) = pts.iter().find(|p| !(p.0.is_finite() && p.1.is_finite())) {
+      return Err(format!("({}, {}) is not a pair of numbers", p.0, p.1));
+    }
+
+    if let Some(w) = pts.windows(2).find(|w| w[1].0 < w[0].0) {
+      return Err(format!("points must be in order, but {} comes after {}", w[1].0, w[0].0));
+    }
+
+    Ok(Self {
+      pts: pts.to_vec()
+    })
+  }
 
+  // the same value everywhere
+  pub fn constant(y : f64) -> Self {
     Self {
-      pts: pts.clone()
+      pts: vec![(0., y)]
     }
   }
 
   pub fn get(&self, x : f64) -> f64 {
     let len = self.pts.len();
+    // only possible if deserialized from something bad
+    if len == 0 { return 0. }
     let min_x = self.pts[0].0;
     let max_x = self.pts[len - 1].0;
 
@@ -37,3 +37,33 @@ pub fn project_to_line(r1 : &Point2<f64>, r2 : &Point2<f64>, p : &Point2<f64>) -
   // projection of pa onto the line
   Some(-pa_dot_n * n)
 }
+
+// closest point to p that lies on the line segment from r1 to r2
+pub fn closest_point_on_segment(r1 : &Point2<f64>, r2 : &Point2<f64>, p : &Point2<f64>) -> Point2<f64> {
+  let v = r2 - r1;
+  let len_sq = v.norm_squared();
+  if len_sq == 0. { return *r1 }
+
+  let t = ((p - r1).dot(&v) / len_sq).clamp(0., 1.);
+  r1 + t * v
+}
+
+// where (as a fraction of the way from p1 to p2) the segment p1 -> p2
+// crosses the segment q1 -> q2. None if they don't cross
+pub fn segment_intersection(p1 : &Point2<f64>, p2 : &Point2<f64>, q1 : &Point2<f64>, q2 : &Point2<f64>) -> Option<f64> {
+  let r = p2 - p1;
+  let s = q2 - q1;
+  let denom = r.perp(&s);
+  // parallel
+  if denom == 0. { return None }
+
+  let qp = q1 - p1;
+  let t = qp.perp(&s) / denom;
+  let u = qp.perp(&r) / denom;
+
+  if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+    Some(t)
+  } else {
+    None
+  }
+}
@@ -0,0 +1,101 @@
+use super::*;
+
+// Declarative description of a step behaviour and its parameters.
+// Every behaviour can describe itself, and be rebuilt from its description,
+// which is what lets us snapshot a simulation's behaviour stack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", deny_unknown_fields)]
+pub enum BehaviourDescriptor {
+  Reset,
+  BasicMove,
+  Wander,
+  Homesick,
+  Satisfied,
+  Scavenge,
+  Starve,
+  OldAge,
+  EdgeHome {
+    #[serde(default)]
+    disabled_edges: Vec<usize>,
+  },
+  NestHome { nest: Option<(f64, f64)> },
+  Predation { size_ratio: f64 },
+}
+
+impl BehaviourDescriptor {
+  pub fn build(&self) -> Box<dyn StepBehaviour> {
+    match self {
+      BehaviourDescriptor::Reset => Box::new(ResetBehaviour),
+      BehaviourDescriptor::BasicMove => Box::new(BasicMoveBehaviour),
+      BehaviourDescriptor::Wander => Box::new(WanderBehaviour),
+      BehaviourDescriptor::Homesick => Box::new(HomesickBehaviour),
+      BehaviourDescriptor::Satisfied => Box::new(SatisfiedBehaviour),
+      BehaviourDescriptor::Scavenge => Box::new(ScavengeBehaviour),
+      BehaviourDescriptor::Starve => Box::new(StarveBehaviour),
+      BehaviourDescriptor::OldAge => Box::new(OldAgeBehaviour),
+      BehaviourDescriptor::EdgeHome { disabled_edges } =>
+        Box::new(EdgeHomeBehaviour { disabled_edges: disabled_edges.clone() }),
+      BehaviourDescriptor::NestHome { nest } =>
+        Box::new(NestHomeBehaviour { nest: nest.map(|(x, y)| Point2::new(x, y)) }),
+      BehaviourDescriptor::Predation { size_ratio } =>
+        Box::new(PredationBehaviour { size_ratio: *size_ratio }),
+    }
+  }
+
+  pub fn name(&self) -> String {
+    serde_json::to_value(self).ok()
+      .and_then(|v| v.get("name").and_then(|n| n.as_str()).map(String::from))
+      .unwrap_or_default()
+  }
+
+  // anything that sends creatures home
+  pub fn is_home(&self) -> bool {
+    matches!(self, BehaviourDescriptor::EdgeHome { .. } | BehaviourDescriptor::NestHome { .. })
+  }
+
+  // catch parameters that are the right type, but make no sense
+  pub fn validate(&self, stage : &dyn Stage) -> Result<(), String> {
+    match self {
+      BehaviourDescriptor::Predation { size_ratio } if !(size_ratio.is_finite() && *size_ratio > 0.) =>
+        Err(format!("size_ratio must be a positive number, not {}", size_ratio)),
+      BehaviourDescriptor::EdgeHome { disabled_edges } => {
+        let edges = stage.get_edges().len();
+        match disabled_edges.iter().find(|e| **e >= edges) {
+          Some(e) => Err(format!("disabled_edges: there is no edge {}, the stage has {}", e, edges)),
+          None => Ok(()),
+        }
+      },
+      BehaviourDescriptor::NestHome { nest: Some((x, y)) } if stage.place(&Point2::new(*x, *y)).is_none() =>
+        Err(format!("nest ({}, {}) is not on the stage", x, y)),
+      _ => Ok(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", deny_unknown_fields)]
+pub enum ReproductionDescriptor {
+  Basic,
+  Sexual { pairing: Pairing, recombination: Recombination },
+}
+
+impl ReproductionDescriptor {
+  pub fn build(&self) -> Box<dyn ReproductionBehaviour> {
+    match self {
+      ReproductionDescriptor::Basic => Box::new(BasicReproductionBehaviour),
+      ReproductionDescriptor::Sexual { pairing, recombination } =>
+        Box::new(SexualReproductionBehaviour { pairing: *pairing, recombination: *recombination }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn edge_home_disables_no_edges_unless_told() {
+    let descriptor : BehaviourDescriptor = serde_json::from_value(serde_json::json!({ "name": "EdgeHome" })).unwrap();
+    assert_eq!(descriptor, BehaviourDescriptor::EdgeHome { disabled_edges: vec![] });
+  }
+}
@@ -33,8 +33,14 @@ impl StepBehaviour for EdgeHomeBehaviour {
   fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
     if let Phase::PRE = phase {
       generation.creatures.iter_mut()
-        .filter(|c| c.is_alive())
+        .filter(|c| c.is_alive() && !c.home_fixed)
         .for_each(|c| self.set_home(c, sim));
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::EdgeHome {
+      disabled_edges: self.disabled_edges.clone(),
+    }
+  }
 }
@@ -4,8 +4,9 @@ use super::*;
 #[derive(Debug, Copy, Clone)]
 pub struct HomesickBehaviour;
 impl HomesickBehaviour {
-  pub fn how_homesick(creature : &Creature) -> Option<Objective> {
-    let dist = (creature.home_pos - creature.get_position()).norm();
+  pub fn how_homesick(creature : &Creature, stage : &dyn Stage) -> Option<Objective> {
+    let home = stage.nearest_image(&creature.get_position(), &creature.home_pos);
+    let dist = (home - creature.get_position()).norm();
     let cost = creature.get_motion_energy_cost();
     let steps_to_home = dist / creature.get_speed();
     let homesick_factor = creature.get_energy_left() / cost - steps_to_home;
@@ -20,7 +21,7 @@ impl HomesickBehaviour {
     };
 
     intensity.map(|intensity| Objective {
-      pos: creature.home_pos,
+      pos: home,
       intensity,
       reason: String::from("low energy"),
     })
@@ -28,17 +29,17 @@ impl HomesickBehaviour {
 }
 
 impl StepBehaviour for HomesickBehaviour {
-  fn apply(&self, phase : Phase, generation : &mut Generation, _sim : &Simulation){
+  fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
     // during orientation...
     if let Phase::ORIENT = phase {
       generation.creatures.iter_mut()
         .filter(|c| c.is_active())
         .filter_map(|c|
-          Self::how_homesick(c)
+          Self::how_homesick(c, &*sim.stage)
             .map(|i| (c, i))
         )
         .for_each(|(c, o)| {
-          if c.can_reach(&c.home_pos) {
+          if c.can_reach(&sim.stage.nearest_image(&c.get_position(), &c.home_pos)) {
             c.sleep();
           } else {
             c.add_objective(o);
@@ -46,4 +47,8 @@ impl StepBehaviour for HomesickBehaviour {
         });
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Homesick
+  }
 }
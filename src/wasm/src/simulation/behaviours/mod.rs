@@ -17,15 +17,35 @@ pub enum Phase {
 pub trait StepBehaviour {
   // if you need &mut self, use Cell or RefCell
   fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation);
+  fn describe(&self) -> BehaviourDescriptor;
+}
+
+// Furthest something can be from a creature and still pass `can_reach`.
+// A little slack makes sure rounding never drops a real candidate from
+// a spatial index query.
+pub fn max_reach_distance(creature : &Creature) -> f64 {
+  let last_step = creature.get_last_position()
+    .map_or(0., |last| (creature.get_position() - last).norm());
+  (creature.get_reach() + last_step) * (1. + 1e-9) + 1e-9
 }
 
 pub trait ReproductionBehaviour {
   fn reproduce(&self, creatures : &Vec<Creature>, sim : &Simulation) -> Vec<Creature>;
+  fn describe(&self) -> ReproductionDescriptor;
 }
 
+mod descriptor;
+pub use descriptor::*;
+
+mod registry;
+pub use registry::*;
+
 mod reproduction;
 pub use reproduction::*;
 
+mod sexual_reproduction;
+pub use sexual_reproduction::*;
+
 mod wander;
 pub use wander::*;
 
@@ -51,9 +71,9 @@ pub use old_age::*;
 mod edge_home;
 pub use edge_home::*;
 
-mod cannibalism;
-pub use cannibalism::*;
+mod nest_home;
+pub use nest_home::*;
+
+mod predation;
+pub use predation::*;
 
-// doesn't work the way i wanted it to
-// mod event;
-// pub use event::*;
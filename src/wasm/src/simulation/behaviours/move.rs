@@ -8,9 +8,15 @@ impl BasicMoveBehaviour {
     // move
     let pos = creature.get_position();
     let new_pos = pos + creature.get_speed() * creature.get_direction().as_ref();
-    let constrained = stage.constrain_within(&new_pos);
+    let constrained = stage.resolve_move(&pos, &new_pos);
 
     creature.move_to( constrained );
+
+    // if it wrapped around, remember where it came from in its new frame
+    let last = stage.nearest_image(&constrained, &pos);
+    if last != pos {
+      creature.set_unwrapped_last_position(last);
+    }
   }
 }
 
@@ -22,4 +28,8 @@ impl StepBehaviour for BasicMoveBehaviour {
         .for_each(|c| self.move_creature(c, &*sim.stage));
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::BasicMove
+  }
 }
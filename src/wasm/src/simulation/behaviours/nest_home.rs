@@ -0,0 +1,26 @@
+use super::*;
+
+// for stages without edges. Home is a fixed nest point, or
+// if there's no nest, wherever the creature happens to be
+#[derive(Debug, Clone)]
+pub struct NestHomeBehaviour {
+  pub nest: Option<Point2<f64>>,
+}
+
+impl StepBehaviour for NestHomeBehaviour {
+  fn apply(&self, phase : Phase, generation : &mut Generation, _sim : &Simulation){
+    if let Phase::PRE = phase {
+      generation.creatures.iter_mut()
+        .filter(|c| c.is_alive() && !c.home_fixed)
+        .for_each(|c| {
+          c.home_pos = self.nest.unwrap_or(c.pos);
+        });
+    }
+  }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::NestHome {
+      nest: self.nest.map(|p| (p.x, p.y)),
+    }
+  }
+}
@@ -21,4 +21,8 @@ impl StepBehaviour for OldAgeBehaviour {
         .for_each(|c| self.check_old_age(c, sim));
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::OldAge
+  }
 }
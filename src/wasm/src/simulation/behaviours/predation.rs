@@ -0,0 +1,181 @@
+use super::*;
+
+// Creatures hunt, flee from and keep away from each other,
+// according to how their species interact (see `Ecology`).
+// Only the bigger of two can eat the other.
+pub struct PredationBehaviour {
+  pub size_ratio : f64, // size ratio for which larger blobs eat smaller blobs
+}
+
+impl PredationBehaviour {
+  // Only pairs within radius of each other are visited, bigger one first. The radius
+  // must cover any pair that func could act on, so that skipping the rest changes nothing.
+  fn for_each_pair(&self, creatures: &mut [Creature], index: &SpatialIndex, radius: f64, func: &mut dyn FnMut(&mut Creature, &mut Creature)) {
+
+    for i in 1..creatures.len(){
+      if !creatures[i].is_active(){ continue; }
+      let nearby = index.query(&creatures[i - 1].get_position(), radius);
+      for j in nearby.into_iter().filter(|j| *j >= i) {
+        let (before, after) = creatures.split_at_mut(j);
+        let other = &mut after[0];
+        if !other.is_active() { continue; }
+        let this = &mut before[i - 1];
+        let (bigger, smaller) = if this.get_size() > other.get_size() {
+          (this, other)
+        } else {
+          (other, this)
+        };
+
+        // some can get killed during this loop... so check again
+        if !bigger.is_active() || !smaller.is_active() { continue }
+
+        func(bigger, smaller);
+      }
+    }
+  }
+
+  // will the bigger one eat the smaller, given the chance?
+  fn hunts(&self, bigger : &Creature, smaller : &Creature, sim : &Simulation) -> bool {
+    bigger.get_size() * self.size_ratio >= smaller.get_size()
+      && sim.ecology.interaction(&bigger.species, &smaller.species) == Interaction::Eat
+  }
+
+  // how `creature` reacts to seeing `other`, unless it's hunting it
+  fn avoid(creature : &mut Creature, other : &Creature, hunted : bool, sim : &Simulation) {
+    let other_pos = sim.stage.nearest_image(&creature.get_position(), &other.get_position());
+    if !sim.stage.has_line_of_sight(&creature.get_position(), &other_pos) { return }
+
+    let interaction = sim.ecology.interaction(&creature.species, &other.species);
+    let flee = match interaction {
+      Interaction::Flee => true,
+      Interaction::Ignore => false,
+      Interaction::Eat | Interaction::Compete => hunted,
+    };
+
+    if flee {
+      if !creature.within_flee_distance(&other_pos) { return }
+      let ang = sim.get_random_float(-FRAC_PI_4, FRAC_PI_4);
+      let rot = na::Rotation2::new(ang);
+      let dir = other_pos - creature.get_position();
+      // this is roughly the position of the predator, but a bit fuzzy
+      // to add an element of randomness
+      let noisy = creature.get_position() + rot * dir;
+      creature.add_objective(Objective {
+        pos: noisy,
+        intensity: ObjectiveIntensity::VitalAversion,
+        reason: String::from("running away"),
+      });
+    } else if interaction == Interaction::Compete {
+      // right on top of each other, there's no way to be further from it
+      if other_pos == creature.get_position() || !creature.can_see(&other_pos) { return }
+      creature.add_objective(Objective {
+        pos: other_pos,
+        intensity: ObjectiveIntensity::MinorAversion,
+        reason: String::from("avoiding rival"),
+      });
+    }
+  }
+
+  fn max_active<F>(creatures : &[Creature], f : F) -> f64
+  where F : Fn(&Creature) -> f64 {
+    creatures.iter()
+      .filter(|c| c.is_active())
+      .map(f)
+      .fold(0., f64::max)
+  }
+}
+
+impl StepBehaviour for PredationBehaviour {
+  fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
+    // Chase...
+    if let Phase::ORIENT = phase {
+      use std::collections::HashMap;
+      let mut target_prey_speed = HashMap::new();
+      // both fleeing and chasing need one to see the other
+      let radius = Self::max_active(&generation.creatures, |c| c.get_sense_range());
+
+      self.for_each_pair(&mut generation.creatures, &generation.creature_index, radius, &mut |bigger, smaller| {
+        let hunts = self.hunts(bigger, smaller, sim);
+        Self::avoid(smaller, bigger, hunts, sim);
+        if !hunts {
+          Self::avoid(bigger, smaller, false, sim);
+          return;
+        }
+
+        let (predator, prey) = (bigger, smaller);
+        // where the prey appears to the predator (differs on stages that wrap)
+        let prey_pos = sim.stage.nearest_image(&predator.get_position(), &prey.get_position());
+        let in_sight = sim.stage.has_line_of_sight(&predator.get_position(), &prey_pos);
+
+        if !in_sight || !predator.can_see(&prey_pos) {
+          // predator can't see prey
+          return;
+        }
+
+        if let Some(prev_speed) = target_prey_speed.get(&predator.id) {
+          if *prev_speed <= prey.get_speed() {
+            // seeing many, so favour the one that's slower
+            return;
+          }
+        }
+
+        target_prey_speed.insert(predator.id, prey.get_speed());
+
+        let intensity = match sim.energy.hunger(predator) {
+          Hunger::Starving => ObjectiveIntensity::VitalCraving,
+          Hunger::Peckish => ObjectiveIntensity::ModerateCraving,
+          Hunger::Full => ObjectiveIntensity::MinorCraving,
+        };
+
+        predator.add_objective(
+          Objective {
+            pos: prey_pos,
+            intensity,
+            reason: String::from("see prey"),
+          });
+      });
+    }
+
+    // Eat...
+    if let Phase::ACT = phase {
+      let steps = generation.steps;
+      let radius = Self::max_active(&generation.creatures, max_reach_distance);
+      self.for_each_pair(&mut generation.creatures, &generation.creature_index, radius, &mut |predator, prey| {
+        if !self.hunts(predator, prey, sim) { return }
+        let prey_pos = sim.stage.nearest_image(&predator.get_position(), &prey.get_position());
+        if !predator.can_reach(&prey_pos) { return }
+
+        // now we can eat it
+        let energy = sim.energy.energy_of(prey);
+        predator.eat_food(steps, prey, energy);
+        prey.kill();
+      });
+    }
+  }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Predation { size_ratio: self.size_ratio }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{World, ScenarioFormat, load_scenario};
+
+  #[test]
+  fn rivals_on_the_same_spot_stay_on_the_stage() {
+    let cfg = load_scenario(include_str!("../../../scenarios/predator_prey.json"), ScenarioFormat::Json).unwrap();
+    let mut world = World::from_config(&cfg).unwrap();
+    world.run(5).unwrap();
+
+    for generation in world.sim.generations.iter() {
+      for creature in generation.creatures.iter() {
+        for p in creature.movement_history.iter() {
+          assert!(p.x.is_finite() && p.y.is_finite(), "{} went to {:?}", creature.id, p);
+          // where a position that isn't anywhere gets clamped to
+          assert!(p.x != 0. || p.y != 0., "{} jumped to the origin", creature.id);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,78 @@
+use super::*;
+use serde_json::Value;
+
+// Every behaviour that can be configured by name. Parameters are
+// given alongside the name, eg: `{ "name": "Predation", "size_ratio": 0.8 }`
+pub const STEP_BEHAVIOURS : [&str; 11] = [
+  "Reset",
+  "BasicMove",
+  "Wander",
+  "Homesick",
+  "Satisfied",
+  "Scavenge",
+  "Starve",
+  "OldAge",
+  "EdgeHome",
+  "NestHome",
+  "Predation",
+];
+
+pub const REPRODUCTION_BEHAVIOURS : [&str; 2] = [
+  "Basic",
+  "Sexual",
+];
+
+fn behaviour_name<'a>(cfg : &'a Value, known : &[&str]) -> Result<&'a str, String> {
+  let name = cfg.get("name")
+    .ok_or_else(|| "behaviour has no name".to_string())?
+    .as_str()
+    .ok_or_else(|| "behaviour name must be a string".to_string())?;
+
+  if !known.contains(&name) {
+    return Err(format!("unknown behaviour `{}`, expected one of: {}", name, known.join(", ")));
+  }
+
+  Ok(name)
+}
+
+// Look up a step behaviour from its config. Parameters that depend on
+// the stage are checked when the stack is built
+pub fn describe_behaviour(cfg : &Value) -> Result<BehaviourDescriptor, String> {
+  let name = behaviour_name(cfg, &STEP_BEHAVIOURS)?;
+  serde_json::from_value(cfg.clone())
+    .map_err(|e| format!("bad parameters for behaviour `{}`: {}", name, e))
+}
+
+pub fn validate_behaviour(descriptor : &BehaviourDescriptor, stage : &dyn Stage) -> Result<(), String> {
+  descriptor.validate(stage)
+    .map_err(|e| format!("bad parameters for behaviour `{}`: {}", descriptor.name(), e))
+}
+
+pub fn describe_reproduction(cfg : &Value) -> Result<ReproductionDescriptor, String> {
+  let name = behaviour_name(cfg, &REPRODUCTION_BEHAVIOURS)?;
+  serde_json::from_value(cfg.clone())
+    .map_err(|e| format!("bad parameters for reproduction behaviour `{}`: {}", name, e))
+}
+
+// The whole stack, in order. Errors say which entry was wrong.
+pub fn build_behaviours(descriptors : &[BehaviourDescriptor], stage : &dyn Stage) -> Result<Vec<Box<dyn StepBehaviour>>, String> {
+  descriptors.iter().enumerate()
+    .map(|(i, d)| {
+      validate_behaviour(d, stage)
+        .map(|_| d.build())
+        .map_err(|e| format!("behaviours[{}]: {}", i, e))
+    })
+    .collect()
+}
+
+// For serde, so a bad stack fails to deserialize and says which entry was wrong
+pub fn deserialize_behaviours<'de, D>(deserializer : D) -> Result<Option<Vec<BehaviourDescriptor>>, D::Error>
+where D : serde::Deserializer<'de> {
+  use serde::{Deserialize, de::Error};
+  let cfgs : Option<Vec<Value>> = Option::deserialize(deserializer)?;
+  cfgs.map(|cfgs| {
+    cfgs.iter().enumerate()
+      .map(|(i, cfg)| describe_behaviour(cfg).map_err(|e| D::Error::custom(format!("behaviours[{}]: {}", i, e))))
+      .collect()
+  }).transpose()
+}
@@ -1,19 +1,13 @@
 use super::*;
-use std::cell::{RefMut};
-use rand::{rngs::SmallRng};
 
 // Basic behaviour for simple movement
 #[derive(Debug, Copy, Clone)]
 pub struct BasicReproductionBehaviour;
 
 impl BasicReproductionBehaviour {
-  pub fn will_reproduce(creature : &Creature) -> bool {
-    creature.foods_eaten.len() > 1
-  }
-
-  fn reproduce(&self, creature : &Creature, rng : &mut RefMut<SmallRng>) -> Vec<Creature> {
-    if Self::will_reproduce(&creature) {
-      vec![creature.mutate(rng)]
+  fn reproduce(&self, creature : &Creature, sim : &Simulation) -> Vec<Creature> {
+    if sim.energy.will_reproduce(creature) {
+      vec![creature.mutate(sim.next_id(), &mut sim.rng.borrow_mut())]
     } else {
       vec![]
     }
@@ -25,11 +19,14 @@ impl ReproductionBehaviour for BasicReproductionBehaviour {
     creatures.iter().filter(|c|
       c.is_alive()
     ).flat_map(|c| {
-      let mut rng = sim.rng.borrow_mut();
-      let mut ctrs = self.reproduce(&c, &mut rng);
+      let mut ctrs = self.reproduce(c, sim);
       let grown = c.grow_older();
       ctrs.push(grown);
       ctrs
     }).collect()
   }
+
+  fn describe(&self) -> ReproductionDescriptor {
+    ReproductionDescriptor::Basic
+  }
 }
@@ -4,39 +4,44 @@ use super::*;
 #[derive(Debug, Copy, Clone)]
 pub struct SatisfiedBehaviour;
 impl SatisfiedBehaviour {
-  fn how_homesick(&self, creature : &Creature) -> Option<Objective> {
+  fn how_homesick(&self, creature : &Creature, sim : &Simulation) -> Option<Objective> {
+    let stage = &*sim.stage;
 
-    match creature.foods_eaten.len() {
-      // if no food... keep going
-      x if x == 0 => None,
-      // if more than 1 foods... go home
-      x if x > 1 => Some(Objective {
-        pos: creature.home_pos,
+    match sim.energy.hunger(creature) {
+      // if not enough food to survive... keep going
+      Hunger::Starving => None,
+      // if enough to reproduce... go home
+      Hunger::Full => Some(Objective {
+        pos: stage.nearest_image(&creature.get_position(), &creature.home_pos),
         intensity: ObjectiveIntensity::MajorCraving,
         reason: String::from("satisfied"),
       }),
-      _ => HomesickBehaviour::how_homesick(&creature),
+      Hunger::Peckish => HomesickBehaviour::how_homesick(creature, stage),
     }
   }
 }
 
 impl StepBehaviour for SatisfiedBehaviour {
-  fn apply(&self, phase : Phase, generation : &mut Generation, _sim : &Simulation){
+  fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
     // during orientation...
     if let Phase::ORIENT = phase {
       generation.creatures.iter_mut()
         .filter(|c| c.is_active())
         .filter_map(|c|
-          self.how_homesick(c)
+          self.how_homesick(c, sim)
             .map(|i| (c, i))
         )
         .for_each(|(c, o)| {
           c.add_objective(o);
 
-          if c.can_reach(&c.home_pos) {
+          if c.can_reach(&sim.stage.nearest_image(&c.get_position(), &c.home_pos)) {
             c.sleep();
           }
         });
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Satisfied
+  }
 }
@@ -4,23 +4,29 @@ use super::*;
 #[derive(Debug, Copy, Clone)]
 pub struct ScavengeBehaviour;
 impl ScavengeBehaviour {
-  fn is_creature_hungry(creature : &Creature) -> bool {
-    creature.is_active() && creature.foods_eaten.len() < 2
+  fn is_creature_hungry(creature : &Creature, energy : &EnergyConfig) -> bool {
+    creature.is_active() && !energy.will_reproduce(creature)
   }
 
-  fn look_for_food(&self, creature : &mut Creature, available_food : &Vec<Food>){
-    if let Some(food) = self.nearest_food(creature, available_food) {
-      if creature.can_see(&food.position) {
+  fn look_for_food(&self, creature : &mut Creature, food : &Vec<Food>, index : &SpatialIndex, sim : &Simulation){
+    let stage = &*sim.stage;
+    let pos = creature.get_position();
+    let mut nearby = index.query(&pos, creature.get_sense_range());
+    // can't see food behind walls
+    nearby.retain(|i| stage.has_line_of_sight(&pos, &stage.nearest_image(&pos, &food[*i].position)));
+    if let Some(food) = self.nearest_food(creature, food, nearby, sim) {
+      let food_pos = stage.nearest_image(&pos, &food.position);
+      if creature.can_see(&food_pos) {
 
         // how hungry is it?
-        let intensity = match creature.foods_eaten.len() {
-          0 => ObjectiveIntensity::VitalCraving,
-          1 => ObjectiveIntensity::ModerateCraving,
-          _ => ObjectiveIntensity::MinorCraving,
+        let intensity = match sim.energy.hunger(creature) {
+          Hunger::Starving => ObjectiveIntensity::VitalCraving,
+          Hunger::Peckish => ObjectiveIntensity::ModerateCraving,
+          Hunger::Full => ObjectiveIntensity::MinorCraving,
         };
 
         creature.add_objective(Objective {
-          pos: food.position,
+          pos: food_pos,
           intensity,
           reason: String::from("see food"),
         });
@@ -28,9 +34,12 @@ impl ScavengeBehaviour {
     }
   }
 
-  fn try_find_food(&self, creature : &Creature, available_food : &Vec<Food>) -> Option<Food> {
-    if let Some(food) = self.nearest_food(creature, available_food) {
-      if !food.is_eaten() && creature.can_reach(&food.position) {
+  fn try_find_food(&self, creature : &Creature, food : &Vec<Food>, index : &SpatialIndex, sim : &Simulation) -> Option<Food> {
+    let stage = &*sim.stage;
+    let pos = creature.get_position();
+    let nearby = index.query(&pos, max_reach_distance(creature));
+    if let Some(food) = self.nearest_food(creature, food, nearby, sim) {
+      if !food.is_eaten() && creature.can_reach(&stage.nearest_image(&pos, &food.position)) {
         return Some(food);
       }
     }
@@ -38,47 +47,47 @@ impl ScavengeBehaviour {
     None
   }
 
-  fn nearest_food(&self, creature : &Creature, available_food : &Vec<Food>) -> Option<Food> {
+  // nearest of the candidates that hasn't been eaten, and is in its diet. Ties go to the lowest index
+  fn nearest_food(&self, creature : &Creature, food : &[Food], candidates : Vec<usize>, sim : &Simulation) -> Option<Food> {
+    let stage = &*sim.stage;
     let pos = creature.get_position();
-    let nearest = available_food.iter()
-      .map(|f| (f.position - pos).norm())
-      .filter(|n| !n.is_nan())
-      .enumerate()
+    let nearest = candidates.into_iter()
+      .filter(|i| !food[*i].is_eaten())
+      .filter(|i| sim.ecology.eats_food(&creature.species, &food[*i]))
+      .map(|i| (i, (stage.nearest_image(&pos, &food[i].position) - pos).norm()))
+      .filter(|(_i, n)| !n.is_nan())
       .min_by(|a, b| (a.1).partial_cmp(&b.1).unwrap());
 
-      if let Some((index, _dist)) = nearest {
-        Some(available_food[index].clone())
-      } else {
-        None
-      }
+    nearest.map(|(index, _dist)| food[index].clone())
   }
 }
 
 impl StepBehaviour for ScavengeBehaviour {
-  fn apply(&self, phase : Phase, generation : &mut Generation, _sim : &Simulation){
+  fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
     if let Phase::ORIENT = phase {
-      let available_food = generation.get_available_food();
+      let food = &generation.food;
+      let index = &generation.food_index;
 
       generation.creatures.iter_mut()
-        .filter(|c| Self::is_creature_hungry(&c))
-        .for_each(|c| self.look_for_food(c, &available_food));
+        .filter(|c| Self::is_creature_hungry(c, &sim.energy))
+        .for_each(|c| self.look_for_food(c, food, index, sim));
     }
 
     // when it is able to interact
     if let Phase::ACT = phase {
-      let mut available_food = generation.get_available_food();
-
       for index in 0..generation.creatures.len() {
         let creature = &mut generation.creatures[index];
-        if Self::is_creature_hungry(&creature) {
-          if let Some(food) = self.try_find_food(creature, &available_food) {
-            creature.eat_food(generation.steps, &food);
+        if Self::is_creature_hungry(creature, &sim.energy) {
+          if let Some(food) = self.try_find_food(creature, &generation.food, &generation.food_index, sim) {
+            creature.eat_food(generation.steps, &food, sim.energy.energy_of(&food));
             generation.mark_food_eaten(&food);
-            let index = available_food.iter().position(|f| *f == food).unwrap();
-            available_food.remove(index);
           }
         }
       }
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Scavenge
+  }
 }
@@ -0,0 +1,92 @@
+use super::*;
+use rand::seq::SliceRandom;
+
+// how mates are found
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pairing {
+  // closest available creature of the same species
+  Nearest,
+  // any available creature of the same species
+  Random,
+}
+
+// Two parents of the same species, with traits recombined then mutated.
+// Each pair has two offspring (one at each parent's home) so populations
+// grow at the same rate as with asexual reproduction. Creatures left
+// without a mate don't reproduce.
+#[derive(Debug, Copy, Clone)]
+pub struct SexualReproductionBehaviour {
+  pub pairing : Pairing,
+  pub recombination : Recombination,
+}
+
+impl SexualReproductionBehaviour {
+  // indices of creatures ready to reproduce, grouped by species
+  // in order of first appearance
+  fn eligible_by_species(creatures : &[Creature], sim : &Simulation) -> Vec<Vec<usize>> {
+    let mut groups : Vec<(&str, Vec<usize>)> = vec![];
+    creatures.iter().enumerate()
+      .filter(|(_i, c)| c.is_alive() && sim.energy.will_reproduce(c))
+      .for_each(|(i, c)| {
+        match groups.iter_mut().find(|(s, _)| *s == c.species) {
+          Some((_, group)) => group.push(i),
+          None => groups.push((&c.species, vec![i])),
+        }
+      });
+
+    groups.into_iter().map(|(_, group)| group).collect()
+  }
+
+  fn pair_up(&self, creatures : &[Creature], mut group : Vec<usize>, sim : &Simulation) -> Vec<(usize, usize)> {
+    match self.pairing {
+      Pairing::Random => {
+        group.shuffle(&mut *sim.rng.borrow_mut());
+        group.chunks_exact(2).map(|p| (p[0], p[1])).collect()
+      },
+      Pairing::Nearest => {
+        let mut pairs = vec![];
+        while group.len() > 1 {
+          let a = group.remove(0);
+          let pos = creatures[a].get_position();
+          let nearest = group.iter().enumerate()
+            .map(|(k, b)| (k, (sim.stage.nearest_image(&pos, &creatures[*b].get_position()) - pos).norm()))
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+            .map(|(k, _)| k)
+            .unwrap();
+
+          pairs.push((a, group.remove(nearest)));
+        }
+        pairs
+      },
+    }
+  }
+}
+
+impl ReproductionBehaviour for SexualReproductionBehaviour {
+  fn reproduce(&self, creatures : &Vec<Creature>, sim : &Simulation) -> Vec<Creature> {
+    let pairs : Vec<(usize, usize)> = Self::eligible_by_species(creatures, sim).into_iter()
+      .flat_map(|group| self.pair_up(creatures, group, sim))
+      .collect();
+
+    let offspring = pairs.into_iter().flat_map(|(a, b)| {
+      let (a, b) = (&creatures[a], &creatures[b]);
+      let first = a.mate(b, self.recombination, sim.next_id(), &mut sim.rng.borrow_mut());
+      let second = b.mate(a, self.recombination, sim.next_id(), &mut sim.rng.borrow_mut());
+      vec![first, second]
+    });
+
+    offspring.chain(
+      creatures.iter()
+        .filter(|c| c.is_alive())
+        .map(|c| c.grow_older())
+    ).collect()
+  }
+
+  fn describe(&self) -> ReproductionDescriptor {
+    ReproductionDescriptor::Sexual {
+      pairing: self.pairing,
+      recombination: self.recombination,
+    }
+  }
+}
@@ -4,15 +4,15 @@ use super::*;
 #[derive(Debug, Copy, Clone)]
 pub struct StarveBehaviour;
 impl StarveBehaviour {
-  fn check_starvation(&self, creature : &mut Creature){
-    if creature.foods_eaten.len() < 1 {
+  fn check_starvation(&self, creature : &mut Creature, energy : &EnergyConfig){
+    if !energy.will_survive(creature) {
       creature.kill();
     }
   }
 }
 
 impl StepBehaviour for StarveBehaviour {
-  fn apply(&self, phase : Phase, generation : &mut Generation, _sim : &Simulation){
+  fn apply(&self, phase : Phase, generation : &mut Generation, sim : &Simulation){
     if let Phase::INIT = phase {
       // if the creature has a speed of zero, it starves. period.
       generation.creatures.iter_mut()
@@ -24,13 +24,13 @@ impl StepBehaviour for StarveBehaviour {
 
     if let Phase::POST = phase {
       let food_available = generation.get_available_food().len();
-      if food_available == 0 {
-        // if there is no food left after this step, check for starvation
+      if food_available == 0 && !sim.food_dynamics.replenishes() {
+        // if there is no food left after this step (and no more coming), check for starvation
         generation.creatures.iter_mut()
           .filter(|c| {
             c.is_active()
           })
-          .for_each(|c| self.check_starvation(c));
+          .for_each(|c| self.check_starvation(c, &sim.energy));
       }
     }
 
@@ -39,7 +39,11 @@ impl StepBehaviour for StarveBehaviour {
         .filter(|c| {
           c.is_alive()
         })
-        .for_each(|c| self.check_starvation(c));
+        .for_each(|c| self.check_starvation(c, &sim.energy));
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Starve
+  }
 }
@@ -31,4 +31,8 @@ impl StepBehaviour for WanderBehaviour {
         });
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Wander
+  }
 }
@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, BTreeSet};
+use super::{Food, FoodKind, Diagnostic};
+
+// What one species does about another when it sees it
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interaction {
+  // hunt it, if it's small enough
+  Eat,
+  // run away from it
+  Flee,
+  Ignore,
+  // after the same things, so keep a little distance
+  Compete,
+}
+
+// What a species will eat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Diet {
+  // food balls
+  pub plants : bool,
+  pub carrion : bool,
+  // species it hunts
+  pub prey : Vec<String>,
+}
+
+impl Default for Diet {
+  fn default() -> Self {
+    Diet { plants: true, carrion: true, prey: vec![] }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeciesConfig {
+  pub diet : Diet,
+  // species it runs from, even if they don't hunt it
+  pub predators : Vec<String>,
+  // overrides whatever the diet and predators imply, by species
+  pub interactions : BTreeMap<String, Interaction>,
+}
+
+impl SpeciesConfig {
+  // any species it names that isn't one of these, by field
+  pub fn problems(&self, known : &BTreeSet<String>) -> Vec<Diagnostic> {
+    let unknown = |path : String, name : &String| {
+      let names : Vec<&str> = known.iter().map(|s| s.as_str()).collect();
+      Diagnostic::new(path, format!("unknown species `{}`, expected one of: {}", name, names.join(", ")))
+    };
+
+    let prey = self.diet.prey.iter().enumerate().map(|(i, name)| (format!("diet.prey[{}]", i), name));
+    let predators = self.predators.iter().enumerate().map(|(i, name)| (format!("predators[{}]", i), name));
+    let interactions = self.interactions.keys().map(|name| (format!("interactions.{}", name), name));
+
+    prey.chain(predators).chain(interactions)
+      .filter(|(_, name)| !known.contains(*name))
+      .map(|(path, name)| unknown(path, name))
+      .collect()
+  }
+}
+
+// How species get along, by species name. Species that aren't
+// configured eat plants, carrion and anything small enough,
+// which is how every creature behaved before there were species.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Ecology(BTreeMap<String, SpeciesConfig>);
+
+impl Ecology {
+  pub fn new(species : BTreeMap<String, SpeciesConfig>) -> Self {
+    Ecology(species)
+  }
+
+  pub fn get(&self, species : &str) -> Option<&SpeciesConfig> {
+    self.0.get(species)
+  }
+
+  fn hunts(&self, species : &str, other : &str) -> bool {
+    self.0.get(species).is_none_or(|cfg| cfg.diet.prey.iter().any(|p| p == other))
+  }
+
+  // what `species` does about `other`
+  pub fn interaction(&self, species : &str, other : &str) -> Interaction {
+    let cfg = match self.0.get(species) {
+      Some(cfg) => cfg,
+      None => return Interaction::Eat,
+    };
+
+    if let Some(i) = cfg.interactions.get(other) {
+      return *i;
+    }
+
+    if self.hunts(species, other) {
+      Interaction::Eat
+    } else if cfg.predators.iter().any(|p| p == other) || self.hunts(other, species) {
+      Interaction::Flee
+    } else {
+      Interaction::Ignore
+    }
+  }
+
+  pub fn eats_food(&self, species : &str, food : &Food) -> bool {
+    self.0.get(species).is_none_or(|cfg| match food.kind {
+      FoodKind::FoodBall => cfg.diet.plants,
+      FoodKind::Carrion => cfg.diet.carrion,
+    })
+  }
+}
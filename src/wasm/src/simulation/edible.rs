@@ -1,7 +1,9 @@
 use uuid::Uuid;
+use super::EnergyConfig;
 pub type FoodType = String;
 
 pub trait Edible {
   fn get_edible_id(&self) -> Uuid;
   fn get_type(&self) -> FoodType;
+  fn get_energy(&self, cfg : &EnergyConfig) -> f64;
 }
\ No newline at end of file
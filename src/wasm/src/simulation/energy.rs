@@ -0,0 +1,95 @@
+use super::{Creature, Edible};
+
+// What eating is worth, and how much energy a creature needs to have left
+// (what it started with, plus what it ate, less what it used) at the end.
+// The defaults make a food ball (or a size 10 creature) worth as much as a
+// creature usually starts with, and a creature needs to end up with that
+// much to survive and twice that to reproduce.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnergyConfig {
+  pub food_energy : f64,
+  // prey are worth this much per unit of their size
+  pub prey_energy_per_size : f64,
+  pub survival_energy : f64,
+  pub reproduction_energy : f64,
+}
+
+impl Default for EnergyConfig {
+  fn default() -> Self {
+    EnergyConfig {
+      food_energy: 500.,
+      prey_energy_per_size: 50.,
+      survival_energy: 500.,
+      reproduction_energy: 1000.,
+    }
+  }
+}
+
+// how badly a creature still needs to eat
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Hunger {
+  // won't survive yet
+  Starving,
+  // will survive, but can't reproduce yet
+  Peckish,
+  // can reproduce
+  Full,
+}
+
+impl EnergyConfig {
+  pub fn energy_of(&self, food : &dyn Edible) -> f64 {
+    food.get_energy(self)
+  }
+
+  pub fn hunger(&self, creature : &Creature) -> Hunger {
+    match creature.get_net_energy() {
+      e if e < self.survival_energy => Hunger::Starving,
+      e if e < self.reproduction_energy => Hunger::Peckish,
+      _ => Hunger::Full,
+    }
+  }
+
+  pub fn will_survive(&self, creature : &Creature) -> bool {
+    self.hunger(creature) != Hunger::Starving
+  }
+
+  pub fn will_reproduce(&self, creature : &Creature) -> bool {
+    self.hunger(creature) == Hunger::Full
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use uuid::Uuid;
+  use na::Point2;
+  use crate::simulation::Food;
+
+  // a creature of this size that ate one food ball, then took
+  // the same walk as the others
+  fn after_a_walk(size : f64, cfg : &EnergyConfig) -> Creature {
+    let mut creature = Creature::default(Uuid::nil(), &Point2::origin());
+    creature.set_trait("size", size);
+    creature.set_speed(10.);
+    creature.set_sense_range(20.);
+    let food = Food::new(Uuid::nil(), Point2::origin(), cfg.food_energy, 1);
+    creature.eat_food(1, &food, cfg.energy_of(&food));
+    for i in 1..=15 {
+      creature.move_to(Point2::new(i as f64 * 10., 0.));
+    }
+    creature
+  }
+
+  #[test]
+  fn costly_movers_need_to_eat_more() {
+    let cfg = EnergyConfig::default();
+    let cheap = after_a_walk(10., &cfg);
+    let costly = after_a_walk(14., &cfg);
+
+    assert_eq!(cheap.energy_eaten, costly.energy_eaten);
+    assert!(costly.is_alive());
+    assert_eq!(cfg.hunger(&cheap), Hunger::Peckish);
+    assert_eq!(cfg.hunger(&costly), Hunger::Starving);
+  }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use super::Step;
+
+// Everything that can go wrong driving a simulation. Errors never leave it
+// half changed, so it's safe to carry on (or snapshot it) after one
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimError {
+  // a bad stage, preset, event, creature, etc. Says which part was wrong
+  InvalidConfig(String),
+  // asked for a generation that hasn't happened (yet)
+  GenerationOutOfRange { index : usize, count : usize },
+  // still going after this many steps. It's left in progress, so it can be
+  // looked at, or intervened in to bring it to an end
+  RunawayGeneration { generation : usize, steps : Step },
+  // something that can't be done to the world as it is right now
+  InvalidOperation(String),
+  // reading or writing json
+  Serialization(String),
+}
+
+pub type SimResult<T> = Result<T, SimError>;
+
+impl fmt::Display for SimError {
+  fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SimError::InvalidConfig(e) => write!(f, "invalid config: {}", e),
+      SimError::GenerationOutOfRange { index, count } =>
+        write!(f, "there is no generation {}, only {} so far", index, count),
+      SimError::RunawayGeneration { generation, steps } =>
+        write!(f, "generation {} was still going after {} steps", generation, steps),
+      SimError::InvalidOperation(e) => write!(f, "{}", e),
+      SimError::Serialization(e) => write!(f, "serialization failed: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for SimError {}
+
+// A problem with one part of a config, and where it is,
+// eg: `creatures[0].template.size`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+  pub path : String,
+  pub message : String,
+}
+
+impl Diagnostic {
+  pub fn new<P : Into<String>, M : Into<String>>(path : P, message : M) -> Self {
+    Diagnostic { path: path.into(), message: message.into() }
+  }
+
+  // the same problem, in something this is part of
+  pub fn within(self, parent : &str) -> Self {
+    let path = match (parent.is_empty(), self.path.is_empty()) {
+      (true, _) => self.path,
+      (false, true) => parent.to_string(),
+      (false, false) if self.path.starts_with('[') => format!("{}{}", parent, self.path),
+      (false, false) => format!("{}.{}", parent, self.path),
+    };
+
+    Diagnostic { path, ..self }
+  }
+
+  // all of them, as one message
+  pub fn join(problems : &[Diagnostic]) -> String {
+    let messages : Vec<String> = problems.iter().map(|d| d.to_string()).collect();
+    messages.join("; ")
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+    if self.path.is_empty() {
+      write!(f, "{}", self.message)
+    } else {
+      write!(f, "{}: {}", self.path, self.message)
+    }
+  }
+}
+
+// all of them, as one error
+impl From<Vec<Diagnostic>> for SimError {
+  fn from(problems : Vec<Diagnostic>) -> SimError {
+    SimError::InvalidConfig(Diagnostic::join(&problems))
+  }
+}
+
+impl From<SimError> for String {
+  fn from(e : SimError) -> String {
+    e.to_string()
+  }
+}
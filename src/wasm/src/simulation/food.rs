@@ -1,11 +1,31 @@
 use uuid::Uuid;
 use na::Point2;
-use super::{Edible, FoodType};
+use super::{Edible, FoodType, EnergyConfig, Step};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FoodStatus {
   Available,
   Eaten(usize), // step the food was eaten at
+  Decayed(usize), // step the food rotted away at
+  Removed(usize), // step it was taken away at, from outside the simulation
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FoodKind {
+  #[default]
+  FoodBall,
+  // remains of a dead creature
+  Carrion,
+}
+
+// what happened to a piece of food, for replaying it
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FoodEvent {
+  Spawned,
+  Eaten,
+  Decayed,
+  Removed,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -13,19 +33,74 @@ pub struct Food {
   pub id : Uuid,
   pub position: Point2<f64>,
   pub status: FoodStatus,
+  #[serde(default)]
+  pub kind: FoodKind,
+  #[serde(default = "default_food_energy")]
+  pub energy: f64,
+  // every change of status, and the step it happened at
+  #[serde(default)]
+  pub status_history: Vec<(Step, FoodEvent)>,
 }
+
+fn default_food_energy() -> f64 { EnergyConfig::default().food_energy }
+
 impl Food {
-  pub fn new(position: Point2<f64>) -> Self {
+  pub fn new(id: Uuid, position: Point2<f64>, energy: f64, step: Step) -> Self {
     Self {
-      id: Uuid::new_v4(),
+      id,
       position,
-      status: FoodStatus::Available
+      status: FoodStatus::Available,
+      kind: FoodKind::FoodBall,
+      energy,
+      status_history: vec![(step, FoodEvent::Spawned)],
     }
   }
+  pub fn carrion(id: Uuid, position: Point2<f64>, energy: f64, step: Step) -> Self {
+    Self {
+      kind: FoodKind::Carrion,
+      ..Self::new(id, position, energy, step)
+    }
+  }
+
   pub fn is_eaten(&self) -> bool { self.status != FoodStatus::Available }
+
+  // how long it has been out, if it hasn't been eaten
+  pub fn available_since(&self) -> Option<Step> {
+    match self.status {
+      FoodStatus::Available => self.status_history.last().map(|(step, _)| *step),
+      _ => None,
+    }
+  }
+
+  pub fn eat(&mut self, step: Step) {
+    self.status = FoodStatus::Eaten(step);
+    self.status_history.push((step, FoodEvent::Eaten));
+  }
+
+  pub fn decay(&mut self, step: Step) {
+    self.status = FoodStatus::Decayed(step);
+    self.status_history.push((step, FoodEvent::Decayed));
+  }
+
+  pub fn remove(&mut self, step: Step) {
+    self.status = FoodStatus::Removed(step);
+    self.status_history.push((step, FoodEvent::Removed));
+  }
+
+  // grow back in the same place
+  pub fn respawn(&mut self, step: Step) {
+    self.status = FoodStatus::Available;
+    self.status_history.push((step, FoodEvent::Spawned));
+  }
 }
 
 impl Edible for Food {
   fn get_edible_id(&self) -> Uuid { self.id }
-  fn get_type(&self) -> FoodType { "food_ball".into() }
-}
\ No newline at end of file
+  fn get_type(&self) -> FoodType {
+    match self.kind {
+      FoodKind::FoodBall => "food_ball".into(),
+      FoodKind::Carrion => "carrion".into(),
+    }
+  }
+  fn get_energy(&self, _cfg : &EnergyConfig) -> f64 { self.energy }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeSet;
+use rand::distributions::{Poisson, Distribution};
+use uuid::Uuid;
+use super::{Generation, Simulation, Food, FoodKind, FoodStatus, Step, Diagnostic};
+
+// Remains left behind when a creature dies (starved, old age, killed, etc).
+// Prey are eaten whole, so they leave nothing behind
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarrionConfig {
+  // worth this much per unit of the dead creature's size
+  pub energy_per_size : f64,
+  // rots away this many steps after the death
+  pub decay_after : Step,
+}
+
+// How food comes and goes during a generation. By default
+// it's all placed at the start and stays until eaten.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodDynamics {
+  // new food per step, on average. Placed the same way as the
+  // food at the start (clustered placements pick new clusters)
+  pub spawn_rate : f64,
+  // eaten food grows back in the same place this many steps later
+  pub regrow_after : Option<Step>,
+  // food nobody eats rots away this many steps after it appeared
+  pub decay_after : Option<Step>,
+  // whether dead creatures leave something behind
+  pub carrion : Option<CarrionConfig>,
+}
+
+impl FoodDynamics {
+  // will there be more food later?
+  pub fn replenishes(&self) -> bool {
+    self.spawn_rate > 0. || self.regrow_after.is_some()
+  }
+
+  // anything that makes no sense, by field
+  pub fn problems(&self) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+    let mut non_negative = |field : &str, v : f64| {
+      if !(v.is_finite() && v >= 0.) {
+        problems.push(Diagnostic::new(field, format!("must be zero or more, not {}", v)));
+      }
+    };
+
+    non_negative("spawn_rate", self.spawn_rate);
+    if let Some(carrion) = self.carrion {
+      non_negative("carrion.energy_per_size", carrion.energy_per_size);
+    }
+
+    problems
+  }
+
+  pub fn apply(&self, generation : &mut Generation, sim : &Simulation) {
+    let step = generation.steps;
+
+    let carrion_decay_after = self.carrion.map(|c| c.decay_after);
+    generation.food.iter_mut()
+      .filter(|f| {
+        let decay_after = match f.kind {
+          FoodKind::FoodBall => self.decay_after,
+          FoodKind::Carrion => carrion_decay_after,
+        };
+        match (f.available_since(), decay_after) {
+          (Some(s), Some(d)) => step - s >= d,
+          _ => false,
+        }
+      })
+      .for_each(|f| f.decay(step));
+
+    if let Some(regrow_after) = self.regrow_after {
+      generation.food.iter_mut()
+        .filter(|f| f.kind == FoodKind::FoodBall)
+        .filter(|f| match f.status {
+          FoodStatus::Eaten(s) => step - s >= regrow_after,
+          _ => false,
+        })
+        .for_each(|f| f.respawn(step));
+    }
+
+    if let Some(carrion) = self.carrion {
+      let eaten : BTreeSet<Uuid> = generation.creatures.iter()
+        .flat_map(|c| c.foods_eaten.iter())
+        .filter(|(_, _, kind)| kind == "creature")
+        .map(|(_, id, _)| *id)
+        .collect();
+      let dead : Vec<(usize, f64)> = generation.creatures.iter().enumerate()
+        .filter(|(_i, c)| !c.is_alive() && !generation.remains_dropped.contains(&c.id))
+        .filter(|(_i, c)| !eaten.contains(&c.id))
+        .map(|(i, c)| (i, c.get_size() * carrion.energy_per_size))
+        .collect();
+
+      for (i, energy) in dead {
+        let c = &generation.creatures[i];
+        generation.remains_dropped.insert(c.id);
+        generation.food.push(Food::carrion(sim.next_id(), c.get_position(), energy, step));
+      }
+    }
+
+    if self.spawn_rate > 0. {
+      let count = Poisson::new(self.spawn_rate).sample(&mut *sim.rng.borrow_mut()) as usize;
+      let positions = sim.food_placement.place(count, &*sim.stage, &mut sim.rng.borrow_mut());
+      generation.food.extend(positions.into_iter().map(|p| {
+        Food::new(sim.next_id(), p, sim.energy.food_energy, step)
+      }));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::na::Point2;
+  use crate::math::Interpolator;
+  use crate::stage::SquareStage;
+  use crate::creature::Creature;
+
+  #[test]
+  fn only_creatures_that_werent_eaten_leave_carrion() {
+    let sim = Simulation::new(Box::new(SquareStage(100.)), 1, Interpolator::constant(0.));
+    let creatures = (0..3).map(|i| Creature::default(sim.next_id(), &Point2::new(10. * i as f64, 10.))).collect();
+    let mut generation = Generation::start(&sim, creatures, vec![]);
+    // the first eats the second, and the third starves
+    let prey = generation.creatures[1].clone();
+    generation.creatures[0].eat_food(1, &prey, 5.);
+    generation.creatures[1].kill();
+    generation.creatures[2].kill();
+
+    let dynamics = FoodDynamics { carrion: Some(CarrionConfig { energy_per_size: 2., decay_after: 10 }), ..FoodDynamics::default() };
+    dynamics.apply(&mut generation, &sim);
+
+    let carrion : Vec<&Food> = generation.food.iter().filter(|f| f.kind == FoodKind::Carrion).collect();
+    assert_eq!(carrion.len(), 1);
+    assert_eq!(carrion[0].position, generation.creatures[2].get_position());
+    assert_eq!(carrion[0].energy, generation.creatures[2].get_size() * 2.);
+  }
+}
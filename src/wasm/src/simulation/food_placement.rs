@@ -0,0 +1,221 @@
+use na::{Point2, Vector2};
+use rand::Rng;
+use rand::distributions::{Normal, Poisson, Distribution};
+use std::cell::{RefMut};
+use std::f64::consts::PI;
+use super::{SimRng, Diagnostic};
+use crate::stage::Stage;
+
+// give up on a spot after this many tries, and just put it anywhere
+const MAX_PLACEMENT_ATTEMPTS : usize = 1_000;
+
+// How food is spread around the stage each generation.
+// Anywhere a strategy picks that's outside the stage (or inside
+// an obstacle) is picked again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FoodPlacement {
+  // anywhere, evenly
+  #[default]
+  Uniform,
+  // around a few randomly placed centres, normally distributed
+  GaussianClusters { clusters : usize, spread : f64 },
+  // a Poisson distributed number of patches (at least one),
+  // with food spread evenly inside a radius of each
+  PoissonPatches { mean_patches : f64, radius : f64 },
+  // more food towards `to` than `from`. Density goes linearly from
+  // `min_density` (relative to the densest end) at `from`, to 1 at `to`
+  Gradient {
+    from : (f64, f64),
+    to : (f64, f64),
+    #[serde(default)]
+    min_density : f64,
+  },
+  // relative densities on a grid from (0, 0), rows along y
+  DensityMap { cell_size : f64, cells : Vec<Vec<f64>> },
+}
+
+impl FoodPlacement {
+  // for a stage that has been scaled by this much
+  pub fn scaled(&self, scale : f64) -> Self {
+    let scale_pt = |(x, y) : (f64, f64)| (x * scale, y * scale);
+    match self {
+      FoodPlacement::Uniform => FoodPlacement::Uniform,
+      FoodPlacement::GaussianClusters { clusters, spread } =>
+        FoodPlacement::GaussianClusters { clusters: *clusters, spread: spread * scale },
+      FoodPlacement::PoissonPatches { mean_patches, radius } =>
+        FoodPlacement::PoissonPatches { mean_patches: *mean_patches, radius: radius * scale },
+      FoodPlacement::Gradient { from, to, min_density } =>
+        FoodPlacement::Gradient { from: scale_pt(*from), to: scale_pt(*to), min_density: *min_density },
+      FoodPlacement::DensityMap { cell_size, cells } =>
+        FoodPlacement::DensityMap { cell_size: cell_size * scale, cells: cells.clone() },
+    }
+  }
+
+  // anything that can't be placed with, by field
+  pub fn problems(&self) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+    let mut non_negative = |field : &str, v : f64| {
+      if !(v.is_finite() && v >= 0.) {
+        problems.push(Diagnostic::new(field, format!("must be zero or more, not {}", v)));
+      }
+    };
+
+    match self {
+      FoodPlacement::Uniform => {},
+      FoodPlacement::GaussianClusters { spread, .. } => non_negative("spread", *spread),
+      FoodPlacement::PoissonPatches { mean_patches, radius } => {
+        non_negative("mean_patches", *mean_patches);
+        non_negative("radius", *radius);
+      },
+      FoodPlacement::Gradient { from, to, min_density } => {
+        for (field, (x, y)) in [("from", from), ("to", to)].iter() {
+          if !(x.is_finite() && y.is_finite()) {
+            problems.push(Diagnostic::new(*field, format!("must be a point, not ({}, {})", x, y)));
+          }
+        }
+        if !(*min_density >= 0. && *min_density <= 1.) {
+          problems.push(Diagnostic::new("min_density", format!("must be between 0 and 1, not {}", min_density)));
+        }
+      },
+      FoodPlacement::DensityMap { cell_size, cells } => {
+        if !(cell_size.is_finite() && *cell_size > 0.) {
+          problems.push(Diagnostic::new("cell_size", format!("must be more than 0, not {}", cell_size)));
+        }
+        for (row, r) in cells.iter().enumerate() {
+          for (col, w) in r.iter().enumerate().filter(|(_, w)| !w.is_finite()) {
+            problems.push(Diagnostic::new(format!("cells[{}][{}]", row, col), format!("must be a number, not {}", w)));
+          }
+        }
+      },
+    }
+
+    problems
+  }
+
+  pub fn place(&self, count : usize, stage : &dyn Stage, rng : &mut RefMut<SimRng>) -> Vec<Point2<f64>> {
+    match self {
+      FoodPlacement::Uniform => {
+        (0..count).map(|_| stage.get_random_location(rng)).collect()
+      },
+      FoodPlacement::GaussianClusters { clusters, spread } => {
+        let centres : Vec<Point2<f64>> = (0..(*clusters).max(1)).map(|_| stage.get_random_location(rng)).collect();
+        let normal = Normal::new(0., *spread);
+
+        (0..count).map(|_| place_with(stage, rng, |rng| {
+          let centre = centres[rng.gen_range(0, centres.len())];
+          Some(centre + Vector2::new(normal.sample(&mut **rng), normal.sample(&mut **rng)))
+        })).collect()
+      },
+      FoodPlacement::PoissonPatches { mean_patches, radius } => {
+        let patches = if *mean_patches > 0. { Poisson::new(*mean_patches).sample(&mut **rng).max(1) } else { 1 };
+        let centres : Vec<Point2<f64>> = (0..patches).map(|_| stage.get_random_location(rng)).collect();
+
+        (0..count).map(|_| place_with(stage, rng, |rng| {
+          let centre = centres[rng.gen_range(0, centres.len())];
+          // sqrt so that they're spread evenly over the patch
+          let r = radius * rng.gen_range(0., 1f64).sqrt();
+          let ang = rng.gen_range(0., 2. * PI);
+          Some(centre + r * Vector2::new(ang.cos(), ang.sin()))
+        })).collect()
+      },
+      FoodPlacement::Gradient { from, to, min_density } => {
+        let from = Point2::new(from.0, from.1);
+        let dir = Point2::new(to.0, to.1) - from;
+        let len2 = dir.norm_squared();
+
+        (0..count).map(|_| place_with(stage, rng, |rng| {
+          // keep uniform locations in proportion to the density there
+          let p = stage.get_random_location(rng);
+          let t = if len2 > 0. { ((p - from).dot(&dir) / len2).clamp(0., 1.) } else { 1. };
+          let density = min_density + (1. - min_density) * t;
+          Some(p).filter(|_| rng.gen_range(0., 1f64) < density)
+        })).collect()
+      },
+      FoodPlacement::DensityMap { cell_size, cells } => {
+        let weighted : Vec<(usize, usize, f64)> = cells.iter().enumerate()
+          .flat_map(|(row, r)| r.iter().enumerate().map(move |(col, w)| (row, col, w.max(0.))))
+          .filter(|(_, _, w)| *w > 0.)
+          .collect();
+        let total : f64 = weighted.iter().map(|(_, _, w)| w).sum();
+
+        if weighted.is_empty() {
+          return FoodPlacement::Uniform.place(count, stage, rng);
+        }
+
+        (0..count).map(|_| place_with(stage, rng, |rng| {
+          // pick a cell by its weight, then anywhere inside it
+          let mut pick = rng.gen_range(0., total);
+          let &(row, col, _) = weighted.iter()
+            .find(|(_, _, w)| { pick -= w; pick < 0. })
+            .unwrap_or(weighted.last().unwrap());
+
+          Some(Point2::new(
+            (col as f64 + rng.gen_range(0., 1f64)) * cell_size,
+            (row as f64 + rng.gen_range(0., 1f64)) * cell_size,
+          ))
+        })).collect()
+      },
+    }
+  }
+}
+
+// keep picking until something is accepted and lands on the stage
+fn place_with<F>(stage : &dyn Stage, rng : &mut RefMut<SimRng>, mut pick : F) -> Point2<f64>
+where F : FnMut(&mut RefMut<SimRng>) -> Option<Point2<f64>> {
+  for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+    if let Some(p) = pick(rng).and_then(|p| stage.place(&p)) {
+      return p;
+    }
+  }
+
+  stage.get_random_location(rng)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::stage::StageConfig;
+
+  fn stage_with(placement : serde_json::Value) -> StageConfig {
+    serde_json::from_value(serde_json::json!({ "shape": "square", "size": 500, "food_placement": placement })).unwrap()
+  }
+
+  #[test]
+  fn bad_parameters_are_errors_when_the_stage_is_built() {
+    let bad = [
+      serde_json::json!({ "type": "gaussian_clusters", "clusters": 3, "spread": -40 }),
+      serde_json::json!({ "type": "poisson_patches", "mean_patches": 2, "radius": -5 }),
+      serde_json::json!({ "type": "poisson_patches", "mean_patches": -1, "radius": 5 }),
+      serde_json::json!({ "type": "gradient", "from": [0, 0], "to": [500, 0], "min_density": 2 }),
+      serde_json::json!({ "type": "density_map", "cell_size": 0, "cells": [[1, 2]] }),
+      serde_json::json!({ "type": "density_map", "cell_size": -10, "cells": [[1, 2]] }),
+    ];
+
+    for placement in bad.iter() {
+      let result = stage_with(placement.clone()).build();
+      assert!(result.is_err(), "{} was accepted", placement);
+      assert!(result.err().unwrap().starts_with("food_placement."));
+    }
+  }
+
+  #[test]
+  fn good_parameters_place_food_on_the_stage() {
+    let good = [
+      serde_json::json!({ "type": "uniform" }),
+      serde_json::json!({ "type": "gaussian_clusters", "clusters": 3, "spread": 0 }),
+      serde_json::json!({ "type": "poisson_patches", "mean_patches": 2, "radius": 20 }),
+      serde_json::json!({ "type": "gradient", "from": [0, 0], "to": [500, 0], "min_density": 0.2 }),
+      serde_json::json!({ "type": "density_map", "cell_size": 100, "cells": [[1, 0], [0, 3]] }),
+    ];
+
+    let rng = std::cell::RefCell::new(<SimRng as rand::SeedableRng>::seed_from_u64(14));
+    for placement in good.iter() {
+      let cfg = stage_with(placement.clone());
+      let stage = cfg.build().unwrap();
+      let placed = cfg.food_placement.place(200, &*stage, &mut rng.borrow_mut());
+      assert_eq!(placed.len(), 200);
+      assert!(placed.iter().all(|p| stage.place(p) == Some(*p)), "{} placed food off the stage", placement);
+    }
+  }
+}
@@ -1,10 +1,12 @@
 use na::Point2;
-use super::{Simulation, Creature, Food, FoodStatus, behaviours::Phase};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+use super::{Simulation, Creature, Food, SpatialIndex, EnvironmentEvent, Invasion, Intervention, InterventionRecord, SimError, SimResult, behaviours::Phase};
 
 pub type Step = usize;
 
-// just to prevent infinite loops
-const MAX_STEPS : usize = 1_000_000;
+// just to prevent infinite loops. Past this it's a runaway generation
+pub const MAX_STEPS : usize = 1_000_000;
 
 // Each generation of the simulation. A collection of creatures
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,32 +14,93 @@ pub struct Generation {
   pub steps : Step, // total steps this generation took to complete
   pub creatures : Vec<Creature>,
   pub food : Vec<Food>, // tuple showing the step the food was eaten
+  // scheduled events that happened, and the step they happened at (0 if before the first)
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub events : Vec<(Step, EnvironmentEvent)>,
+  // creatures that arrived at the start of it
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub invasions : Vec<Invasion>,
+  // everything done to it from outside while it was in progress
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub interventions : Vec<InterventionRecord>,
+
+  // rebuilt before the ORIENT and ACT phases of every step
+  #[serde(skip)]
+  pub creature_index : SpatialIndex,
+  #[serde(skip)]
+  pub food_index : SpatialIndex,
+  // creatures that have already left carrion behind
+  #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+  pub remains_dropped : BTreeSet<Uuid>,
 }
 
 impl Generation {
-  pub fn new( sim : &Simulation, creatures: Vec<Creature>, food_locations: Vec<Point2<f64>> ) -> Self {
+  pub fn new( sim : &Simulation, creatures: Vec<Creature>, food_locations: Vec<Point2<f64>> ) -> SimResult<Self> {
 
     Generation::generate(sim, creatures, food_locations)
   }
 
-  fn generate(sim : &Simulation, creatures: Vec<Creature>, food_locations: Vec<Point2<f64>>) -> Self {
+  fn generate(sim : &Simulation, creatures: Vec<Creature>, food_locations: Vec<Point2<f64>>) -> SimResult<Self> {
+    let mut gen = Generation::start(sim, creatures, food_locations);
+    gen.advance(sim, usize::MAX)?;
+    gen.finish(sim);
+
+    Ok(gen)
+  }
+
+  // Set up a generation without running any of it. Stepping it
+  // along with `advance` then `finish` ends up the same as `new`
+  pub fn start(sim : &Simulation, creatures: Vec<Creature>, food_locations: Vec<Point2<f64>>) -> Self {
     let food = food_locations.iter().map(|p| {
-      Food::new(*p)
+      Food::new(sim.next_id(), *p, sim.energy.food_energy, 1)
     }).collect();
 
     let mut gen = Generation {
       creatures,
       food,
       steps: 1,
+      events: vec![],
+      invasions: vec![],
+      interventions: vec![],
+      creature_index: SpatialIndex::default(),
+      food_index: SpatialIndex::default(),
+      remains_dropped: BTreeSet::new(),
     };
 
     gen.run_phase(Phase::INIT, sim);
-    gen.step_to_completion(sim);
-    gen.run_phase(Phase::FINAL, sim);
 
     gen
   }
 
+  // run at most this many steps. True once nobody is active, and it's ready to finish.
+  // A runaway generation stops at the last step it got to
+  pub fn advance(&mut self, sim : &Simulation, steps : usize) -> SimResult<bool> {
+    for _ in 0..steps {
+      if !self.has_active_creatures() { break; }
+      if self.steps >= MAX_STEPS {
+        return Err(SimError::RunawayGeneration { generation: sim.generations.len(), steps: self.steps });
+      }
+      self.step(sim);
+    }
+
+    Ok(!self.has_active_creatures())
+  }
+
+  // act on it from outside, before the next step. Kept in `interventions`
+  pub fn intervene(&mut self, intervention : Intervention, sim : &Simulation) -> SimResult<Option<Uuid>> {
+    let created = intervention.apply(self, sim).map_err(SimError::InvalidOperation)?;
+    self.interventions.push(InterventionRecord {
+      step: self.steps,
+      intervention,
+      created,
+    });
+    Ok(created)
+  }
+
+  pub fn finish(&mut self, sim : &Simulation) {
+    self.run_phase(Phase::FINAL, sim);
+  }
+
   pub fn has_living_creatures(&self) -> bool {
     self.creatures.iter().any(|c| c.is_alive())
   }
@@ -53,16 +116,32 @@ impl Generation {
   }
 
   pub fn mark_food_eaten(&mut self, food : &Food){
-    if let Some(index) = self.food.iter().position(|f| *f == *food) {
-      self.food[index].status = FoodStatus::Eaten(self.steps);
+    let step = self.steps;
+    if let Some(f) = self.food.iter_mut().find(|f| f.id == food.id) {
+      f.eat(step);
     }
   }
 
-  // advance the generation to its end
-  fn step_to_completion(&mut self, sim : &Simulation) {
-    // break when all asleep or dead
-    while self.has_active_creatures() {
-      self.step(sim);
+  // index creature and food positions for neighbour queries
+  pub fn update_spatial_indices(&mut self, sim : &Simulation){
+    let active = self.creatures.iter().filter(|c| c.is_active());
+    let (total, count) = active.fold((0., 0), |(t, n), c| (t + c.get_sense_range(), n + 1));
+    // cells about the size of what a creature can see
+    let cell_size = if count > 0 { total / count as f64 } else { 1. };
+
+    let period = sim.stage.wrap_period();
+
+    self.creature_index = SpatialIndex::new(self.creatures.iter().map(|c| c.get_position()), cell_size, period);
+    self.food_index = SpatialIndex::new(self.food.iter().map(|f| f.position), cell_size, period);
+  }
+
+  // anything scheduled for this step
+  fn apply_events(&mut self, sim : &Simulation){
+    let index = sim.generations.len();
+    let step = self.steps;
+    for scheduled in sim.timeline.iter().filter(|e| e.generation == index && e.during_step() == Some(step)) {
+      scheduled.event.apply_to_generation(self, sim);
+      self.events.push((step, scheduled.event.clone()));
     }
   }
 
@@ -77,13 +156,15 @@ impl Generation {
   }
 
   fn step(&mut self, sim : &Simulation){
-    assert!(self.steps < MAX_STEPS);
-
     // let _timer = Timer::new(String::from("Step"));
+    self.apply_events(sim);
     self.run_phase(Phase::PRE, sim);
+    self.update_spatial_indices(sim);
     self.run_phase(Phase::ORIENT, sim);
     self.run_phase(Phase::MOVE, sim);
+    self.update_spatial_indices(sim);
     self.run_phase(Phase::ACT, sim);
+    sim.food_dynamics.apply(self, sim);
     self.run_phase(Phase::POST, sim);
 
     self.steps += 1;
@@ -0,0 +1,24 @@
+use uuid::Uuid;
+
+// Hands out ids derived from the simulation seed. The seed fills the
+// upper half of the uuid and a counter the lower half, so a given seed
+// and config always produce identical ids.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdGenerator {
+  seed : u64,
+  count : u64,
+}
+
+impl IdGenerator {
+  pub fn new(seed : u64) -> Self {
+    Self {
+      seed,
+      count: 0,
+    }
+  }
+
+  pub fn next_id(&mut self) -> Uuid {
+    self.count += 1;
+    Uuid::from_u128(((self.seed as u128) << 64) | self.count as u128)
+  }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use na::Point2;
+use uuid::Uuid;
+use super::{Simulation, Generation, Creature, CreatureTemplate, Gene, Food, Step, Diagnostic};
+
+// Something done to a generation from outside, part way through it
+// eg: `{ "type": "drop_food", "position": [100, 250] }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Intervention {
+  // a food ball, worth the usual energy if not given
+  DropFood {
+    position : (f64, f64),
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    energy : Option<f64>,
+  },
+  RemoveFood { id : Uuid },
+  KillCreature { id : Uuid },
+  SpawnCreature { position : (f64, f64), template : CreatureTemplate },
+  // set some of its traits to these values
+  EditTraits { id : Uuid, traits : BTreeMap<String, f64> },
+  // how much it has left to move with
+  SetEnergy { id : Uuid, energy : f64 },
+  // for the rest of the generation, whatever its home behaviour says
+  MoveHome { id : Uuid, position : (f64, f64) },
+}
+
+// An intervention, the step it happened before,
+// and the id of anything it created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterventionRecord {
+  pub step : Step,
+  #[serde(flatten)]
+  pub intervention : Intervention,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub created : Option<Uuid>,
+}
+
+fn finite(name : &str, value : f64) -> Result<f64, String> {
+  if value.is_finite() { Ok(value) } else { Err(format!("{} must be a number, not {}", name, value)) }
+}
+
+fn non_negative(name : &str, value : f64) -> Result<f64, String> {
+  let value = finite(name, value)?;
+  if value < 0. { Err(format!("{} must not be negative, not {}", name, value)) } else { Ok(value) }
+}
+
+impl Intervention {
+  // Apply it to the generation in progress, between steps. Returns what
+  // was created, if anything. Nothing changes if there's an error
+  pub fn apply(&self, generation : &mut Generation, sim : &Simulation) -> Result<Option<Uuid>, String> {
+    let step = generation.steps;
+    let on_stage = |(x, y) : (f64, f64)| {
+      sim.stage.place(&Point2::new(x, y))
+        .ok_or_else(|| format!("({}, {}) is not on the stage", x, y))
+    };
+
+    match self {
+      Intervention::DropFood { position, energy } => {
+        let position = on_stage(*position)?;
+        let energy = non_negative("energy", energy.unwrap_or(sim.energy.food_energy))?;
+        let food = Food::new(sim.next_id(), position, energy, step);
+        let id = food.id;
+        generation.food.push(food);
+        Ok(Some(id))
+      },
+      Intervention::RemoveFood { id } => {
+        let food = generation.food.iter_mut()
+          .find(|f| f.id == *id && !f.is_eaten())
+          .ok_or_else(|| format!("no food {} left to remove", id))?;
+        food.remove(step);
+        Ok(None)
+      },
+      Intervention::KillCreature { id } => {
+        let creature = living_creature(generation, id)?;
+        creature.kill();
+        Ok(None)
+      },
+      Intervention::SpawnCreature { position, template } => {
+        let position = on_stage(*position)?;
+        let problems : Vec<Diagnostic> = template.problems().into_iter().map(|d| d.within("template")).collect();
+        if !problems.is_empty() {
+          return Err(Diagnostic::join(&problems));
+        }
+        let mut creature = template.build(sim.next_id(), &position);
+        // as if it had been standing there since the start, so replays line up
+        creature.movement_history = vec![position; step];
+        let id = creature.id;
+        generation.creatures.push(creature);
+        Ok(Some(id))
+      },
+      Intervention::EditTraits { id, traits } => {
+        let creature = living_creature(generation, id)?;
+        // only traits it has, and within their bounds
+        for (name, value) in traits {
+          let gene = creature.get_genome().get(name)
+            .ok_or_else(|| format!("{} has no trait {}", id, name))?;
+          let edited = Gene { value: *value, ..gene.clone() };
+          let problems : Vec<Diagnostic> = edited.problems(name).into_iter()
+            .filter(|d| d.path.is_empty())
+            .map(|d| d.within(name))
+            .collect();
+          if !problems.is_empty() {
+            return Err(Diagnostic::join(&problems));
+          }
+        }
+        for (name, value) in traits {
+          creature.set_trait(name, *value);
+        }
+        Ok(None)
+      },
+      Intervention::SetEnergy { id, energy } => {
+        let energy = non_negative("energy", *energy)?;
+        living_creature(generation, id)?.energy = energy;
+        Ok(None)
+      },
+      Intervention::MoveHome { id, position } => {
+        let position = on_stage(*position)?;
+        let creature = living_creature(generation, id)?;
+        creature.home_pos = position;
+        creature.home_fixed = true;
+        Ok(None)
+      },
+    }
+  }
+}
+
+fn living_creature<'a>(generation : &'a mut Generation, id : &Uuid) -> Result<&'a mut Creature, String> {
+  generation.creatures.iter_mut()
+    .find(|c| c.id == *id && c.is_alive())
+    .ok_or_else(|| format!("no living creature {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::api::{World, WorldConfig};
+  use crate::simulation::{SimError, SimResult};
+
+  // a world part way into its first generation, and one of its creatures
+  fn world() -> (World, Uuid) {
+    let cfg : WorldConfig = serde_json::from_value(serde_json::json!({
+      "stage": { "shape": "square", "size": 300 },
+      "seed": 3,
+      "food_per_generation": [[0, 20]],
+      "preset": { "name": "default" },
+      "creatures": [{ "count": 5, "template": { "species": "default", "sense_range": { "value": 50, "variance": 1, "min": 10, "max": 100 } } }],
+    })).unwrap();
+    let mut world = World::from_config(&cfg).unwrap();
+    world.start_generation().unwrap();
+    let id = world.get_generation_in_progress().unwrap().creatures[0].id;
+    (world, id)
+  }
+
+  fn edit(trait_name : &str, value : f64) -> SimResult<()> {
+    let (mut world, id) = world();
+    let traits = vec![(trait_name.to_string(), value)].into_iter().collect();
+    world.intervene(Intervention::EditTraits { id, traits }).map(|_| ())
+  }
+
+  #[test]
+  fn traits_can_be_edited_within_their_bounds() {
+    assert!(edit("sense_range", 80.).is_ok());
+    assert!(edit("speed", 3.).is_ok());
+  }
+
+  #[test]
+  fn editing_a_trait_it_does_not_have_is_an_error() {
+    assert!(matches!(edit("sped", 3.), Err(SimError::InvalidOperation(_))));
+  }
+
+  #[test]
+  fn editing_a_trait_out_of_bounds_is_an_error() {
+    assert!(matches!(edit("sense_range", 5.), Err(SimError::InvalidOperation(_))));
+    assert!(matches!(edit("sense_range", 101.), Err(SimError::InvalidOperation(_))));
+    assert!(matches!(edit("size", 0.), Err(SimError::InvalidOperation(_))));
+    assert!(matches!(edit("speed", f64::NAN), Err(SimError::InvalidOperation(_))));
+  }
+
+  #[test]
+  fn energy_can_not_be_set_negative() {
+    let (mut world, id) = world();
+    assert!(world.intervene(Intervention::SetEnergy { id, energy: 0. }).is_ok());
+    assert!(matches!(world.intervene(Intervention::SetEnergy { id, energy: -1. }), Err(SimError::InvalidOperation(_))));
+  }
+
+  #[test]
+  fn food_can_not_be_dropped_with_negative_energy() {
+    let (mut world, _) = world();
+    let drop = |energy : f64| Intervention::DropFood { position: (100., 100.), energy: Some(energy) };
+    assert!(world.intervene(drop(0.)).is_ok());
+    assert!(matches!(world.intervene(drop(-1.)), Err(SimError::InvalidOperation(_))));
+  }
+}
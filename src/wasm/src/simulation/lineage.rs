@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+use super::Generation;
+
+// Family tree of every creature that has appeared in a simulation.
+// Creatures keep their id as they age, so each one is only
+// counted once, from the generation it first appeared in.
+#[derive(Debug, Clone, Default)]
+pub struct Genealogy {
+  // in the order they were first seen
+  ids : Vec<Uuid>,
+  parents : HashMap<Uuid, Vec<Uuid>>,
+  children : HashMap<Uuid, Vec<Uuid>>,
+  born : HashMap<Uuid, usize>,
+}
+
+impl Genealogy {
+  pub fn new(generations : &[Generation]) -> Self {
+    let mut genealogy = Genealogy::default();
+
+    for (index, generation) in generations.iter().enumerate() {
+      for c in &generation.creatures {
+        if genealogy.born.contains_key(&c.id) { continue; }
+
+        genealogy.ids.push(c.id);
+        genealogy.born.insert(c.id, index);
+        genealogy.parents.insert(c.id, c.parents.clone());
+        for p in &c.parents {
+          genealogy.children.entry(*p).or_insert_with(Vec::new).push(c.id);
+        }
+      }
+    }
+
+    genealogy
+  }
+
+  fn parents_of(&self, id : &Uuid) -> &[Uuid] {
+    self.parents.get(id).map_or(&[], |p| p.as_slice())
+  }
+
+  fn children_of(&self, id : &Uuid) -> &[Uuid] {
+    self.children.get(id).map_or(&[], |c| c.as_slice())
+  }
+
+  // everyone reachable by following `next`, nearest first
+  fn walk<'a, F>(&'a self, id : &Uuid, next : F) -> Vec<Uuid>
+  where F : Fn(&'a Self, &Uuid) -> &'a [Uuid] {
+    let mut seen = HashSet::new();
+    let mut found = vec![];
+    let mut queue : VecDeque<Uuid> = next(self, id).iter().cloned().collect();
+
+    while let Some(other) = queue.pop_front() {
+      if !seen.insert(other) { continue; }
+      found.push(other);
+      queue.extend(next(self, &other).iter().cloned());
+    }
+
+    found
+  }
+
+  pub fn ancestors(&self, id : &Uuid) -> Vec<Uuid> {
+    self.walk(id, Self::parents_of)
+  }
+
+  pub fn descendants(&self, id : &Uuid) -> Vec<Uuid> {
+    self.walk(id, Self::children_of)
+  }
+
+  // the ancestors that have no known parents (or itself, if it is one)
+  pub fn founders(&self, id : &Uuid) -> Vec<Uuid> {
+    let mut founders : Vec<Uuid> = self.ancestors(id).into_iter()
+      .filter(|a| self.parents_of(a).is_empty())
+      .collect();
+
+    if founders.is_empty() {
+      founders.push(*id);
+    }
+
+    founders
+  }
+
+  // how many of the founders still have a surviving descendant,
+  // for each generation
+  pub fn surviving_lineages(&self, generations : &[Generation]) -> Vec<usize> {
+    let mut founders_of : HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+
+    generations.iter().map(|generation| {
+      let mut lineages = HashSet::new();
+      for c in generation.creatures.iter().filter(|c| c.is_alive()) {
+        let founders = founders_of.entry(c.id).or_insert_with(|| self.founders(&c.id));
+        lineages.extend(founders.iter().cloned());
+      }
+      lineages.len()
+    }).collect()
+  }
+
+  // what fraction of each generation is one of the founders or descended
+  // from them. Any creature with at least one such parent counts
+  pub fn lineage_share(&self, founders : &[Uuid], generations : &[Generation]) -> Vec<f64> {
+    let mut lineage : HashSet<Uuid> = founders.iter().cloned().collect();
+    for f in founders {
+      lineage.extend(self.descendants(f));
+    }
+
+    generations.iter().map(|generation| {
+      let population = generation.creatures.len();
+      let count = generation.creatures.iter().filter(|c| lineage.contains(&c.id)).count();
+      if population > 0 { count as f64 / population as f64 } else { 0. }
+    }).collect()
+  }
+
+  // The whole genealogy as a Newick tree, with branch lengths in generations.
+  // Newick can only give each node one parent, so creatures with two
+  // are placed under the first (the one they were born beside).
+  pub fn to_newick(&self) -> String {
+    let first_parent = |id : &Uuid| self.parents_of(id).first().filter(|p| self.born.contains_key(p)).cloned();
+
+    let mut tree_children : HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    let mut roots = vec![];
+    for id in &self.ids {
+      match first_parent(id) {
+        Some(p) => tree_children.entry(p).or_default().push(*id),
+        None => roots.push(*id),
+      }
+    }
+
+    let subtrees : Vec<String> = roots.iter()
+      .map(|r| self.newick_subtree(r, &tree_children))
+      .collect();
+
+    match subtrees.len() {
+      1 => format!("{};", subtrees[0]),
+      _ => format!("({});", subtrees.join(",")),
+    }
+  }
+
+  fn newick_subtree(&self, id : &Uuid, tree_children : &HashMap<Uuid, Vec<Uuid>>) -> String {
+    let label = id.to_hyphenated().to_string();
+    let children = match tree_children.get(id) {
+      Some(c) => c,
+      None => return label,
+    };
+
+    let subtrees : Vec<String> = children.iter().map(|c| {
+      let length = self.born[c] - self.born[id];
+      format!("{}:{}", self.newick_subtree(c, tree_children), length)
+    }).collect();
+
+    format!("({}){}", subtrees.join(","), label)
+  }
+}
@@ -2,8 +2,9 @@ extern crate rand;
 use crate::na::{Point2};
 use std::cell::{RefCell};
 use std::rc::Rc;
-use rand::{SeedableRng, Rng, rngs::SmallRng};
+use rand::{SeedableRng, Rng};
 use rand::distributions::{Normal, Distribution};
+use uuid::Uuid;
 
 use crate::math::Interpolator;
 use crate::creature::*;
@@ -16,9 +17,33 @@ mod food;
 pub use food::*;
 mod generation;
 pub use generation::*;
+mod id_generator;
+pub use id_generator::*;
+mod lineage;
+pub use lineage::*;
+mod energy;
+pub use energy::*;
+mod food_placement;
+pub use food_placement::*;
+mod food_dynamics;
+pub use food_dynamics::*;
+mod ecology;
+pub use ecology::*;
+mod timeline;
+pub use timeline::*;
+mod intervention;
+pub use intervention::*;
+mod error;
+pub use error::*;
+mod spatial_index;
+pub use spatial_index::*;
 
 pub mod behaviours;
-use behaviours::Phase;
+use behaviours::{Phase, BehaviourDescriptor, ReproductionDescriptor};
+
+// This is what SmallRng resolves to on wasm32. Naming it explicitly
+// keeps native runs identical to the browser, and lets us serialize it.
+pub type SimRng = rand_pcg::Pcg32;
 
 // behaviour to reset parameters on step
 #[derive(Debug, Copy, Clone)]
@@ -38,16 +63,76 @@ impl behaviours::StepBehaviour for ResetBehaviour {
         });
     }
   }
+
+  fn describe(&self) -> BehaviourDescriptor {
+    BehaviourDescriptor::Reset
+  }
+}
+
+// Everything needed to pick up a simulation where it left off.
+// Callbacks can't be captured, so they need to be re-registered
+// on the simulation being restored into.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationSnapshot {
+  #[serde(with = "rng_as_string")]
+  pub rng : SimRng,
+  pub ids : IdGenerator,
+  pub food_per_generation : Interpolator,
+  pub generations : Vec<Generation>,
+  pub behaviours : Vec<BehaviourDescriptor>,
+  pub reproduction_behaviour : ReproductionDescriptor,
+  #[serde(default)]
+  pub energy : EnergyConfig,
+  #[serde(default)]
+  pub food_placement : FoodPlacement,
+  #[serde(default)]
+  pub food_dynamics : FoodDynamics,
+  #[serde(default)]
+  pub ecology : Ecology,
+  #[serde(default)]
+  pub timeline : Vec<ScheduledEvent>,
+  // as it is now, which events may have changed
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub stage_config : Option<StageConfig>,
+}
+
+// The rng state is made of u64s, which javascript numbers can't hold
+// without losing precision. So keep it as a json string instead.
+mod rng_as_string {
+  use super::SimRng;
+  use serde::{Serializer, Deserializer, Deserialize, de::Error, ser};
+
+  pub fn serialize<S : Serializer>(rng : &SimRng, serializer : S) -> Result<S::Ok, S::Error> {
+    let s = serde_json::to_string(rng).map_err(ser::Error::custom)?;
+    serializer.serialize_str(&s)
+  }
+
+  pub fn deserialize<'de, D : Deserializer<'de>>(deserializer : D) -> Result<SimRng, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    serde_json::from_str(&s).map_err(D::Error::custom)
+  }
 }
 
 pub struct Simulation {
-  pub rng : Rc<RefCell<SmallRng>>,
+  pub rng : Rc<RefCell<SimRng>>,
+  // creature and food ids, derived from the seed
+  pub ids : RefCell<IdGenerator>,
   // Area this simulation occurs in
   pub stage : Box<dyn Stage>,
+  // what the stage was built from, if known. Needed to resize it
+  pub stage_config : Option<StageConfig>,
   pub food_per_generation : Interpolator,
   pub generations : Vec<Generation>,
   pub behaviours : Vec<Box<dyn behaviours::StepBehaviour>>,
   pub reproduction_behaviour : Box<dyn behaviours::ReproductionBehaviour>,
+  // what food is worth and how much creatures need
+  pub energy : EnergyConfig,
+  pub food_placement : FoodPlacement,
+  pub food_dynamics : FoodDynamics,
+  // who eats and runs from whom, by species
+  pub ecology : Ecology,
+  // changes to the environment, by generation
+  pub timeline : Vec<ScheduledEvent>,
   callbacks : Vec<Box<dyn FnMut(&mut Simulation) -> ()>>,
 }
 
@@ -63,8 +148,15 @@ impl Simulation {
       food_per_generation,
       behaviours : vec![Box::new(ResetBehaviour)],
       reproduction_behaviour : Box::new(behaviours::BasicReproductionBehaviour),
+      energy : EnergyConfig::default(),
+      food_placement : FoodPlacement::default(),
+      food_dynamics : FoodDynamics::default(),
+      ecology : Ecology::default(),
+      timeline : vec![],
+      stage_config : None,
       // prepare a deterministic generator:
-      rng: Rc::new(RefCell::new(SmallRng::seed_from_u64(seed))),
+      rng: Rc::new(RefCell::new(SimRng::seed_from_u64(seed))),
+      ids: RefCell::new(IdGenerator::new(seed)),
       callbacks: vec![],
     }
   }
@@ -93,33 +185,107 @@ impl Simulation {
     std::mem::swap(&mut self.callbacks, &mut cbs);
   }
 
-  pub fn run(&mut self, creatures: Vec<Creature>, max_generations : u32){
-    let mut generation = Generation::new(self, creatures, self.generate_food());
-    let mut keep_going = generation.has_living_creatures();
+  pub fn snapshot(&self) -> SimulationSnapshot {
+    SimulationSnapshot {
+      rng: self.rng.borrow().clone(),
+      ids: self.ids.borrow().clone(),
+      food_per_generation: self.food_per_generation.clone(),
+      generations: self.generations.clone(),
+      behaviours: self.behaviours.iter().map(|b| b.describe()).collect(),
+      reproduction_behaviour: self.reproduction_behaviour.describe(),
+      energy: self.energy,
+      food_placement: self.food_placement.clone(),
+      food_dynamics: self.food_dynamics.clone(),
+      ecology: self.ecology.clone(),
+      timeline: self.timeline.clone(),
+      stage_config: self.stage_config.clone(),
+    }
+  }
+
+  pub fn restore(&mut self, snapshot : SimulationSnapshot) -> SimResult<()> {
+    // the stage itself needs rebuilding if an event changed it
+    if let Some(cfg) = &snapshot.stage_config {
+      self.stage = cfg.build().map_err(|e| SimError::InvalidConfig(format!("stage: {}", e)))?;
+      self.stage_config = snapshot.stage_config;
+    }
+    *self.rng.borrow_mut() = snapshot.rng;
+    *self.ids.borrow_mut() = snapshot.ids;
+    self.food_per_generation = snapshot.food_per_generation;
+    self.generations = snapshot.generations;
+    self.behaviours = snapshot.behaviours.iter().map(|b| b.build()).collect();
+    self.reproduction_behaviour = snapshot.reproduction_behaviour.build();
+    self.energy = snapshot.energy;
+    self.food_placement = snapshot.food_placement;
+    self.food_dynamics = snapshot.food_dynamics;
+    self.ecology = snapshot.ecology;
+    self.timeline = snapshot.timeline;
+    Ok(())
+  }
+
+  // what's scheduled for before the next generation starts
+  fn events_before_next(&self) -> Vec<EnvironmentEvent> {
+    let index = self.generations.len();
+    self.timeline.iter()
+      .filter(|e| e.generation == index && e.during_step().is_none())
+      .map(|e| e.event.clone())
+      .collect()
+  }
+
+  // can everything scheduled for before the next generation happen?
+  pub fn check_next_generation(&self) -> SimResult<()> {
+    check_events(self.generations.len(), &self.events_before_next(), self)
+  }
+
+  // Apply whatever is scheduled for before the next generation, then set it
+  // up without running any of it. Step it along with `Generation::advance`.
+  // Check it can be with `check_next_generation` first
+  pub fn begin_generation(&mut self, mut creatures : Vec<Creature>) -> Generation {
+    let events = self.events_before_next();
 
-    for _gen in 1..max_generations {
-      if !keep_going { break; }
+    let mut invasions = vec![];
+    for event in &events {
+      let before = creatures.len();
+      event.apply_to_simulation(self, &mut creatures);
 
-      let creatures = self.exec_reproduction(&generation.creatures);
-      self.generations.push(generation);
-      self.call_callbacks();
-      let next = Generation::new( self, creatures, self.generate_food() );
-      generation = next;
-      keep_going = generation.has_living_creatures();
+      if let EnvironmentEvent::Invaders(invasion) = event {
+        invasions.push(Invasion {
+          group: invasion.group(),
+          founders: creatures[before..].iter().map(|c| c.id).collect(),
+        });
+      }
     }
 
+    let mut generation = Generation::start(self, creatures, self.generate_food());
+    generation.invasions = invasions;
+    // before the first step
+    generation.events = events.into_iter().map(|e| (0, e)).collect();
+    generation
+  }
+
+  // keep a finished generation, and breed the creatures for the next
+  pub fn end_generation(&mut self, generation : Generation) -> Vec<Creature> {
+    let creatures = self.exec_reproduction(&generation.creatures);
     self.generations.push(generation);
     self.call_callbacks();
+    creatures
   }
 
   pub fn exec_reproduction(&self, creatures : &Vec<Creature>) -> Vec<Creature> {
     self.reproduction_behaviour.reproduce(&creatures, &self)
   }
 
+  pub fn next_id(&self) -> Uuid {
+    self.ids.borrow_mut().next_id()
+  }
+
   pub fn get_random_location(&self) -> Point2<f64> {
     self.stage.get_random_location(&mut self.rng.borrow_mut())
   }
 
+  pub fn get_random_edge_point(&self) -> Point2<f64> {
+    self.stage.get_random_edge_point(&mut self.rng.borrow_mut())
+  }
+
   pub fn get_random_float(&self, from : f64, to : f64) -> f64 {
     let mut rng = self.rng.borrow_mut();
     rng.gen_range(from, to)
@@ -132,10 +298,8 @@ impl Simulation {
 
   pub fn generate_food(&self) -> Vec<Point2<f64>> {
     let gen = self.generations.len();
-    let num_foods = self.food_per_generation.get(gen as f64).round() as u32;
-    (0..num_foods).map(|_n|
-      self.get_random_location()
-    ).collect()
+    let num_foods = self.food_per_generation.get(gen as f64).round() as usize;
+    self.food_placement.place(num_foods, &*self.stage, &mut self.rng.borrow_mut())
   }
 }
 
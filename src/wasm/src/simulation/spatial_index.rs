@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use na::{Point2, Vector2};
+
+// Uniform grid over item indices, for "everything within radius r of p" queries.
+// It only narrows down candidates. Callers still apply their own exact checks
+// (can_see, can_reach...) so results match a scan over every item.
+// On stages that wrap around, queries also look across the seams.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+  cell_size : f64,
+  period : Option<f64>,
+  positions : Vec<Point2<f64>>,
+  cells : HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialIndex {
+  pub fn new<I>(positions : I, cell_size : f64, period : Option<f64>) -> Self
+  where I : IntoIterator<Item = Point2<f64>> {
+    let cell_size = if cell_size.is_finite() && cell_size > 0. { cell_size } else { 1. };
+    let positions : Vec<Point2<f64>> = positions.into_iter().collect();
+    let mut cells = HashMap::new();
+
+    for (index, p) in positions.iter().enumerate() {
+      let key = Self::cell_of(cell_size, p.x, p.y);
+      cells.entry(key).or_insert_with(Vec::new).push(index);
+    }
+
+    Self {
+      cell_size,
+      period,
+      positions,
+      cells,
+    }
+  }
+
+  fn cell_of(cell_size : f64, x : f64, y : f64) -> (i64, i64) {
+    ((x / cell_size).floor() as i64, (y / cell_size).floor() as i64)
+  }
+
+  // indices of every item within radius of pos, in ascending order
+  pub fn query(&self, pos : &Point2<f64>, radius : f64) -> Vec<usize> {
+    match self.period {
+      None => self.query_around(pos, radius),
+      Some(l) => {
+        let shifts = [-l, 0., l];
+        let mut found : Vec<usize> = shifts.iter()
+          .flat_map(|dx| shifts.iter().map(move |dy| Vector2::new(*dx, *dy)))
+          .flat_map(|shift| self.query_around(&(pos + shift), radius))
+          .collect();
+
+        found.sort_unstable();
+        found.dedup();
+        found
+      },
+    }
+  }
+
+  fn query_around(&self, pos : &Point2<f64>, radius : f64) -> Vec<usize> {
+    let (min_x, min_y) = Self::cell_of(self.cell_size, pos.x - radius, pos.y - radius);
+    let (max_x, max_y) = Self::cell_of(self.cell_size, pos.x + radius, pos.y + radius);
+    let num_cells = max_x.saturating_sub(min_x).saturating_add(1)
+      .saturating_mul(max_y.saturating_sub(min_y).saturating_add(1));
+
+    let within = |index : &usize| (self.positions[*index] - pos).norm() <= radius;
+    let mut found : Vec<usize> = if num_cells as usize > self.cells.len() {
+      // the query covers more cells than are occupied, so just check them all
+      self.cells.values().flat_map(|c| c.iter()).cloned().filter(within).collect()
+    } else {
+      let mut found = vec![];
+      for x in min_x..=max_x {
+        for y in min_y..=max_y {
+          if let Some(cell) = self.cells.get(&(x, y)) {
+            found.extend(cell.iter().cloned().filter(within));
+          }
+        }
+      }
+      found
+    };
+
+    found.sort_unstable();
+    found
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::{Rng, SeedableRng};
+  use crate::simulation::SimRng;
+
+  fn random_positions(rng : &mut SimRng, count : usize, size : f64) -> Vec<Point2<f64>> {
+    (0..count).map(|_| Point2::new(rng.gen_range(0., size), rng.gen_range(0., size))).collect()
+  }
+
+  // what a scan over every item finds
+  fn brute_force(positions : &[Point2<f64>], pos : &Point2<f64>, radius : f64, period : Option<f64>) -> Vec<usize> {
+    let shifts : Vec<Vector2<f64>> = match period {
+      None => vec![Vector2::new(0., 0.)],
+      Some(l) => {
+        let offsets = [-l, 0., l];
+        offsets.iter().flat_map(|dx| offsets.iter().map(move |dy| Vector2::new(*dx, *dy))).collect()
+      },
+    };
+
+    (0..positions.len())
+      .filter(|i| shifts.iter().any(|shift| (positions[*i] - (pos + shift)).norm() <= radius))
+      .collect()
+  }
+
+  #[test]
+  fn query_matches_brute_force() {
+    let mut rng = SimRng::seed_from_u64(4);
+    let size = 500.;
+
+    for period in [None, Some(size)].iter() {
+      for cell_size in [7., 25., 100.].iter() {
+        let positions = random_positions(&mut rng, 400, size);
+        let index = SpatialIndex::new(positions.clone(), *cell_size, *period);
+
+        for query in random_positions(&mut rng, 50, size) {
+          for radius in [0., 5., 30., 120., 1e6].iter() {
+            assert_eq!(
+              index.query(&query, *radius),
+              brute_force(&positions, &query, *radius, *period),
+              "at {:?} within {} (cells of {}, period {:?})", query, radius, cell_size, period
+            );
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn bad_cell_size_still_finds_everything() {
+    let mut rng = SimRng::seed_from_u64(5);
+    let positions = random_positions(&mut rng, 100, 50.);
+    let query = Point2::new(25., 25.);
+
+    for cell_size in [0., -3., f64::NAN].iter() {
+      let index = SpatialIndex::new(positions.clone(), *cell_size, None);
+      assert_eq!(index.query(&query, 10.), brute_force(&positions, &query, 10., None));
+    }
+  }
+}
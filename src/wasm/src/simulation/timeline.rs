@@ -0,0 +1,327 @@
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+use crate::math::Interpolator;
+use super::{Simulation, Generation, Creature, CreatureTemplate, FoodPlacement, Step, Diagnostic, SimError, SimResult};
+use super::behaviours::{BehaviourDescriptor, EdgeHomeBehaviour, STEP_BEHAVIOURS};
+use crate::stage::Stage;
+
+// A group of creatures arriving from elsewhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvasionConfig {
+  // to tell them apart when reporting how they did. Their species, if not given
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub group : Option<String>,
+  pub count : usize,
+  pub template : CreatureTemplate,
+  // where they turn up, the same way food is placed. At the edges, if not given
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub placement : Option<FoodPlacement>,
+}
+
+impl InvasionConfig {
+  pub fn group(&self) -> String {
+    self.group.clone().unwrap_or_else(|| self.template.species.clone())
+  }
+}
+
+// Who arrived in an invasion, so their descendants can be followed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invasion {
+  pub group : String,
+  pub founders : Vec<Uuid>,
+}
+
+// A change to the environment. Most change how the simulation is set up,
+// so they happen before a generation starts. Mass mortality happens
+// part way through one, at the start of the given step (the first by default).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnvironmentEvent {
+  // food per generation from now on
+  FoodLevel { food : f64 },
+  // add a behaviour, at a position in the preset's list of behaviours (or at the end)
+  EnableBehaviour {
+    behaviour : BehaviourDescriptor,
+    #[serde(default)]
+    position : Option<usize>,
+  },
+  // remove every behaviour with this name
+  DisableBehaviour { name : String },
+  // creatures can no longer go home to these edges
+  DisableEdges { edges : Vec<usize> },
+  // new creatures
+  Invaders(InvasionConfig),
+  // kill this fraction of the living creatures
+  MassMortality { fraction : f64 },
+  // grow or shrink the stage (and everything on it) about the origin
+  ResizeStage { scale : f64 },
+}
+
+// An event, and when it happens. Generations are counted from 0.
+// eg: `{ "generation": 10, "step": 50, "type": "mass_mortality", "fraction": 0.5 }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+  pub generation : usize,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub step : Option<Step>,
+  #[serde(flatten)]
+  pub event : EnvironmentEvent,
+}
+
+impl ScheduledEvent {
+  // the step of the generation it happens at, or None if before it starts
+  pub fn during_step(&self) -> Option<Step> {
+    match self.event {
+      EnvironmentEvent::MassMortality { .. } => Some(self.step.unwrap_or(1)),
+      _ => None,
+    }
+  }
+
+  // the species it brings in, if any
+  pub fn species(&self) -> Option<&str> {
+    match &self.event {
+      EnvironmentEvent::Invaders(invasion) => Some(&invasion.template.species),
+      _ => None,
+    }
+  }
+
+  // anything wrong with it, by field
+  pub fn problems(&self, stage : &dyn Stage) -> Vec<Diagnostic> {
+    if self.step.is_some() && self.during_step().is_none() {
+      return vec![Diagnostic::new("step", "only mass_mortality can happen part way through a generation, so it can't have a step")];
+    }
+
+    let problem = match &self.event {
+      EnvironmentEvent::FoodLevel { food } if !(food.is_finite() && *food >= 0.) =>
+        Diagnostic::new("food", format!("must be zero or more, not {}", food)),
+      EnvironmentEvent::EnableBehaviour { behaviour, .. } => match behaviour.validate(stage) {
+        Err(e) => Diagnostic::new("behaviour", format!("bad parameters for behaviour `{}`: {}", behaviour.name(), e)),
+        Ok(_) => return vec![],
+      },
+      EnvironmentEvent::DisableBehaviour { name } if !STEP_BEHAVIOURS.contains(&name.as_str()) =>
+        Diagnostic::new("name", format!("unknown behaviour `{}`, expected one of: {}", name, STEP_BEHAVIOURS.join(", "))),
+      EnvironmentEvent::DisableEdges { edges } => {
+        let count = stage.get_edges().len();
+        match edges.iter().position(|e| *e >= count) {
+          Some(i) => Diagnostic::new(format!("edges[{}]", i), format!("there is no edge {}, the stage has {}", edges[i], count)),
+          None => return vec![],
+        }
+      },
+      EnvironmentEvent::Invaders(invasion) => {
+        let mut problems : Vec<Diagnostic> = invasion.template.problems().into_iter().map(|d| d.within("template")).collect();
+        if let Some(placement) = &invasion.placement {
+          problems.extend(placement.problems().into_iter().map(|d| d.within("placement")));
+        }
+        if invasion.count == 0 {
+          problems.insert(0, Diagnostic::new("count", "must be at least 1"));
+        }
+        return problems;
+      },
+      EnvironmentEvent::MassMortality { fraction } if !(*fraction >= 0. && *fraction <= 1.) =>
+        Diagnostic::new("fraction", format!("must be between 0 and 1, not {}", fraction)),
+      EnvironmentEvent::ResizeStage { scale } if !(scale.is_finite() && *scale > 0.) =>
+        Diagnostic::new("scale", format!("must be a positive number, not {}", scale)),
+      _ => return vec![],
+    };
+
+    vec![problem]
+  }
+}
+
+// Whether these events can all happen before the next generation, in order.
+// Checked before any of them do, so a failure leaves the simulation as it was
+pub fn check_events(generation : usize, events : &[EnvironmentEvent], sim : &Simulation) -> SimResult<()> {
+  let mut homes : Vec<String> = sim.behaviours.iter()
+    .map(|b| b.describe())
+    .filter(|b| b.is_home())
+    .map(|b| b.name())
+    .collect();
+  let mut stage_config = sim.stage_config.clone();
+
+  for event in events {
+    match event {
+      EnvironmentEvent::EnableBehaviour { behaviour, .. } if behaviour.is_home() => homes.push(behaviour.name()),
+      EnvironmentEvent::DisableBehaviour { name } => homes.retain(|h| h != name),
+      // a nest has no edges to disable
+      EnvironmentEvent::DisableEdges { .. } if !homes.iter().any(|h| h == "EdgeHome") =>
+        return Err(SimError::InvalidConfig(format!("generation {}: disable_edges: there's no EdgeHome behaviour to disable edges of", generation))),
+      EnvironmentEvent::ResizeStage { scale } => {
+        let resized = stage_config.as_ref()
+          .ok_or_else(|| SimError::InvalidOperation(format!("generation {}: resize_stage: the stage was made without a config to resize", generation)))?
+          .scaled(*scale);
+        resized.build().map_err(|e| SimError::InvalidConfig(format!("generation {}: resize_stage: {}", generation, e)))?;
+        stage_config = Some(resized);
+      },
+      _ => {},
+    }
+  }
+
+  Ok(())
+}
+
+impl EnvironmentEvent {
+  // before a generation starts, with the creatures about to take part in it.
+  // Anything that could go wrong is caught by `check_events` first
+  pub fn apply_to_simulation(&self, sim : &mut Simulation, creatures : &mut Vec<Creature>) {
+    match self {
+      EnvironmentEvent::FoodLevel { food } => {
+        sim.food_per_generation = Interpolator::constant(*food);
+      },
+      EnvironmentEvent::EnableBehaviour { behaviour, position } => {
+        // the reset behaviour always comes first
+        let len = sim.behaviours.len();
+        let at = position.map_or(len, |p| (p + 1).min(len));
+        sim.behaviours.insert(at, behaviour.build());
+      },
+      EnvironmentEvent::DisableBehaviour { name } => {
+        sim.behaviours.retain(|b| b.describe().name() != *name);
+      },
+      // on top of any already disabled
+      EnvironmentEvent::DisableEdges { edges } => {
+        for b in sim.behaviours.iter_mut() {
+          if let BehaviourDescriptor::EdgeHome { mut disabled_edges } = b.describe() {
+            for e in edges {
+              if !disabled_edges.contains(e) {
+                disabled_edges.push(*e);
+              }
+            }
+            *b = Box::new(EdgeHomeBehaviour { disabled_edges });
+          }
+        }
+      },
+      EnvironmentEvent::Invaders(invasion) => {
+        let positions = match &invasion.placement {
+          Some(placement) => placement.place(invasion.count, &*sim.stage, &mut sim.rng.borrow_mut()),
+          None => (0..invasion.count)
+            .map(|_| sim.get_random_edge_point())
+            .collect(),
+        };
+
+        for pos in positions {
+          creatures.push(invasion.template.build(sim.next_id(), &pos));
+        }
+      },
+      EnvironmentEvent::ResizeStage { scale } => {
+        let resized = match sim.stage_config.as_ref().map(|cfg| cfg.scaled(*scale)) {
+          Some(cfg) => cfg,
+          None => return,
+        };
+
+        if let Ok(stage) = resized.build() {
+          sim.stage = stage;
+          sim.food_placement = sim.food_placement.scaled(*scale);
+          sim.stage_config = Some(resized);
+          for c in creatures.iter_mut() {
+            *c = c.with_new_position(c.id, &(c.get_position() * *scale));
+          }
+        }
+      },
+      EnvironmentEvent::MassMortality { .. } => {},
+    }
+  }
+
+  // part way through a generation
+  pub fn apply_to_generation(&self, generation : &mut Generation, sim : &Simulation) {
+    if let EnvironmentEvent::MassMortality { fraction } = self {
+      let living : Vec<usize> = generation.creatures.iter().enumerate()
+        .filter(|(_i, c)| c.is_alive())
+        .map(|(i, _c)| i)
+        .collect();
+      let count = (*fraction * living.len() as f64).round() as usize;
+      let victims : Vec<usize> = living.choose_multiple(&mut *sim.rng.borrow_mut(), count).cloned().collect();
+
+      for i in victims {
+        generation.creatures[i].kill();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{World, WorldConfig};
+  use crate::stage::SquareStage;
+
+  fn world(behaviours : serde_json::Value, events : serde_json::Value) -> World {
+    let cfg : WorldConfig = serde_json::from_value(serde_json::json!({
+      "stage": { "shape": "square", "size": 300 },
+      "seed": 19,
+      "food_per_generation": [[0, 40]],
+      "preset": { "name": "default", "behaviours": behaviours },
+      "creatures": [{ "count": 20, "template": {} }],
+      "events": events,
+    })).unwrap();
+    World::from_config(&cfg).unwrap()
+  }
+
+  fn names(sim : &Simulation) -> Vec<String> {
+    sim.behaviours.iter().map(|b| b.describe().name()).collect()
+  }
+
+  #[test]
+  fn only_mass_mortality_can_have_a_step() {
+    let stage = SquareStage(100.);
+    let event = |event : serde_json::Value| serde_json::from_value::<ScheduledEvent>(event).unwrap();
+
+    let food = event(serde_json::json!({ "generation": 1, "step": 5, "type": "food_level", "food": 10 }));
+    assert_eq!(food.problems(&stage)[0].path, "step");
+
+    let mortality = event(serde_json::json!({ "generation": 1, "step": 5, "type": "mass_mortality", "fraction": 0.5 }));
+    assert!(mortality.problems(&stage).is_empty());
+  }
+
+  #[test]
+  fn disabling_edges_keeps_the_behaviour_order() {
+    let behaviours = serde_json::json!([{ "name": "BasicMove" }, { "name": "EdgeHome" }, { "name": "Starve" }]);
+    let mut world = world(behaviours, serde_json::json!([{ "generation": 0, "type": "disable_edges", "edges": [0] }]));
+    let before = names(&world.sim);
+    world.run(1).unwrap();
+
+    assert_eq!(names(&world.sim), before);
+    assert_eq!(world.sim.behaviours[2].describe(), BehaviourDescriptor::EdgeHome { disabled_edges: vec![0] });
+  }
+
+  #[test]
+  fn disabling_edges_without_a_home_is_an_error() {
+    let behaviours = serde_json::json!([{ "name": "BasicMove" }, { "name": "Starve" }]);
+    let mut world = world(behaviours, serde_json::json!([{ "generation": 0, "type": "disable_edges", "edges": [0] }]));
+    let before = names(&world.sim);
+
+    assert!(matches!(world.run(1), Err(SimError::InvalidConfig(_))));
+    assert_eq!(world.sim.generations.len(), 0);
+    assert_eq!(names(&world.sim), before);
+    // and it's left ready to try again
+    assert!(world.can_continue());
+  }
+
+  #[test]
+  fn disabling_more_edges_adds_to_those_already_disabled() {
+    let behaviours = serde_json::json!([{ "name": "BasicMove" }, { "name": "EdgeHome", "disabled_edges": [1] }, { "name": "Starve" }]);
+    let events = serde_json::json!([
+      { "generation": 0, "type": "disable_edges", "edges": [0, 1] },
+      { "generation": 0, "type": "disable_edges", "edges": [3] },
+    ]);
+    let mut world = world(behaviours, events);
+    world.run(1).unwrap();
+
+    assert_eq!(world.sim.behaviours[2].describe(), BehaviourDescriptor::EdgeHome { disabled_edges: vec![1, 0, 3] });
+  }
+
+  #[test]
+  fn disabling_edges_of_a_nest_is_an_error() {
+    let behaviours = serde_json::json!([{ "name": "BasicMove" }, { "name": "NestHome", "nest": [150, 150] }, { "name": "Starve" }]);
+    let mut world = world(behaviours, serde_json::json!([{ "generation": 0, "type": "disable_edges", "edges": [0] }]));
+
+    assert!(matches!(world.run(1), Err(SimError::InvalidConfig(_))));
+    assert_eq!(world.sim.behaviours[2].describe(), BehaviourDescriptor::NestHome { nest: Some((150., 150.)) });
+  }
+
+  #[test]
+  fn resizing_a_stage_without_its_config_is_an_error() {
+    let mut sim = Simulation::new(Box::new(SquareStage(100.)), 1, Interpolator::constant(10.));
+    sim.timeline.push(serde_json::from_value(serde_json::json!({ "generation": 0, "type": "resize_stage", "scale": 2 })).unwrap());
+
+    assert!(matches!(sim.check_next_generation(), Err(SimError::InvalidOperation(_))));
+  }
+}
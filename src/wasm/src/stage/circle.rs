@@ -0,0 +1,119 @@
+use super::*;
+use std::f64::consts::PI;
+use crate::math::closest_point_on_segment;
+
+// number of edges used to approximate the circumference
+const CIRCLE_EDGES : usize = 64;
+
+// circle of given radius, sitting in the same [0, 2r] box a square would.
+// Its boundary is the polygon its edges make, so creatures are kept
+// within (and go home to) exactly the edges it has
+pub struct CircleStage(pub f64);
+
+impl CircleStage {
+  fn vertex(&self, i : usize) -> Point2<f64> {
+    let ang = 2. * PI * ((i % CIRCLE_EDGES) as f64) / (CIRCLE_EDGES as f64);
+    self.get_center() + self.0 * Vector2::new(ang.cos(), ang.sin())
+  }
+
+  // the edge facing the same way from the center as the position
+  fn edge_towards(&self, pos : &Point2<f64>) -> usize {
+    let d = pos - self.get_center();
+    let ang = d.y.atan2(d.x).rem_euclid(2. * PI);
+    ((ang / (2. * PI) * CIRCLE_EDGES as f64) as usize).min(CIRCLE_EDGES - 1)
+  }
+
+  fn contains(&self, pos : &Point2<f64>) -> bool {
+    // it's convex, so inside means inside the edge facing it
+    let i = self.edge_towards(pos);
+    let (a, b) = (self.vertex(i), self.vertex(i + 1));
+    (b - a).perp(&(pos - a)) >= 0.
+  }
+}
+
+impl Stage for CircleStage {
+
+  fn get_edges(&self) -> Vec<Edge> {
+    (0..CIRCLE_EDGES).map(|i| {
+      Edge(self.vertex(i), self.vertex(i + 1))
+    }).collect()
+  }
+
+  fn can_move_to(&self, to : &Point2<f64>, _creature : &Creature ) -> bool {
+    self.contains(to)
+  }
+
+  fn get_center(&self) -> Point2<f64> { Point2::new(self.0, self.0) }
+
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    // nearly all of the circle is inside its edges, so this rarely goes round again
+    loop {
+      // sqrt so that points aren't bunched up in the middle
+      let r = self.0 * rng.gen_range(0., 1f64).sqrt();
+      let ang = rng.gen_range(0., 2. * PI);
+      let p = self.get_center() + r * Vector2::new(ang.cos(), ang.sin());
+
+      if self.contains(&p) {
+        return p;
+      }
+    }
+  }
+
+  fn get_nearest_edge_point(&self, pos : &Point2<f64>) -> Point2<f64> {
+    if *pos == self.get_center() {
+      return self.vertex(0);
+    }
+
+    // the edge facing it, or one either side of that near a corner
+    let i = self.edge_towards(pos) + CIRCLE_EDGES;
+    (i - 1..=i + 1)
+      .map(|i| closest_point_on_segment(&self.vertex(i), &self.vertex(i + 1), pos))
+      .map(|p| (p, (p - pos).norm()))
+      .min_by(|a, b| a.1.total_cmp(&b.1))
+      .map_or(self.vertex(0), |(p, _)| p)
+  }
+
+  fn constrain_within(&self, pos : &Point2<f64>) -> Point2<f64> {
+    if self.contains(pos) {
+      *pos
+    } else {
+      self.get_nearest_edge_point(pos)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::math::distance_to_line;
+
+  fn on_an_edge(stage : &CircleStage, p : &Point2<f64>) -> bool {
+    stage.get_edges().iter().any(|e| distance_to_line(&e.0, &e.1, p).is_some_and(|d| d < 1e-9))
+  }
+
+  #[test]
+  fn its_edges_are_its_boundary() {
+    let stage = CircleStage(100.);
+    let c = stage.get_center();
+    for k in 0..360 {
+      let ang = (k as f64).to_radians();
+      let towards = Vector2::new(ang.cos(), ang.sin());
+      // just inside the circle, but outside its edges where they cut across
+      let between = c + 99.99 * towards;
+      let outside = c + 150. * towards;
+
+      let nearest = stage.get_nearest_edge_point(&between);
+      assert!(on_an_edge(&stage, &nearest), "{} isn't on an edge", nearest);
+      assert!(on_an_edge(&stage, &stage.get_nearest_edge_point(&outside)));
+      assert!(on_an_edge(&stage, &stage.constrain_within(&outside)));
+      if !stage.contains(&between) {
+        assert_eq!(stage.constrain_within(&between), nearest);
+      }
+    }
+
+    let midway = (stage.vertex(0).coords + stage.vertex(1).coords) / 2.;
+    let past_the_edge = Point2::from(midway + 0.01 * Vector2::x());
+    assert!(!stage.contains(&past_the_edge));
+    assert!(stage.contains(&c));
+  }
+}
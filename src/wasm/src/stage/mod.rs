@@ -1,7 +1,8 @@
-use crate::na::{Point2};
+use crate::na::{Point2, Vector2};
 use std::cell::{RefMut};
-use rand::{Rng, rngs::SmallRng};
+use rand::Rng;
 use super::creature::*;
+use super::simulation::{SimRng, FoodPlacement, FoodDynamics, Diagnostic};
 // The stage defines the borders of the simulation
 // It's the area the creatures can evolve inside
 
@@ -14,11 +15,178 @@ pub trait Stage {
   fn can_move_to(&self, to : &Point2<f64>, creature : &Creature ) -> bool;
   fn get_center(&self) -> Point2<f64>;
   // generate a location from a u64 seed. used to randomly place food within boundaries
-  fn get_random_location(&self, rng : &mut RefMut<SmallRng>) -> Point2<f64>;
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64>;
   fn get_nearest_edge_point(&self, pos : &Point2<f64>) -> Point2<f64>;
+  // somewhere along the edge, for a creature to start from
+  fn get_random_edge_point(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    let p = self.get_random_location(rng);
+    self.get_nearest_edge_point(&p)
+  }
   fn constrain_within(&self, pos : &Point2<f64>) -> Point2<f64>;
+  // where something trying to move in a straight line actually ends up
+  fn resolve_move(&self, _from : &Point2<f64>, to : &Point2<f64>) -> Point2<f64> {
+    self.constrain_within(to)
+  }
+  // can something at one point see another?
+  fn has_line_of_sight(&self, _from : &Point2<f64>, _to : &Point2<f64>) -> bool { true }
+  // the copy of `to` nearest to `from`. Only differs on stages that wrap around
+  fn nearest_image(&self, _from : &Point2<f64>, to : &Point2<f64>) -> Point2<f64> { *to }
+  // size of the repeating area, on stages that wrap around
+  fn wrap_period(&self) -> Option<f64> { None }
+  // where something placed at this point ends up, if it can be placed there at all
+  fn place(&self, pos : &Point2<f64>) -> Option<Point2<f64>> {
+    Some(*pos).filter(|p| self.constrain_within(p) == *p)
+  }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum StageShape {
+  Square { size : f64 },
+  Circle { radius : f64 },
+  // counter clockwise
+  Polygon { vertices : Vec<(f64, f64)> },
+  // square that wraps around at the edges
+  Torus { size : f64 },
 }
 
+// Which stage to build, as passed in from configuration
+// eg: `{ "shape": "square", "size": 500, "obstacles": [[[100, 100], [200, 100], [150, 200]]] }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageConfig {
+  #[serde(flatten)]
+  pub shape : StageShape,
+  // polygons inside the stage that nothing can enter
+  #[serde(default)]
+  pub obstacles : Vec<Vec<(f64, f64)>>,
+  #[serde(default)]
+  pub obstacles_block_vision : bool,
+  // how food is spread around the stage, eg: `{ "type": "gaussian_clusters", "clusters": 3, "spread": 40 }`
+  #[serde(default)]
+  pub food_placement : FoodPlacement,
+  // whether food respawns, regrows or decays during a generation
+  #[serde(default)]
+  pub food_dynamics : FoodDynamics,
+}
+
+fn to_points(vertices : &[(f64, f64)]) -> Vec<Point2<f64>> {
+  vertices.iter().map(|(x, y)| Point2::new(*x, *y)).collect()
+}
+
+impl StageConfig {
+  // the same stage, grown or shrunk about the origin. Food is placed the same way, relative to it
+  pub fn scaled(&self, scale : f64) -> Self {
+    let scale_all = |vertices : &Vec<(f64, f64)>| vertices.iter().map(|(x, y)| (x * scale, y * scale)).collect();
+    let shape = match &self.shape {
+      StageShape::Square { size } => StageShape::Square { size: size * scale },
+      StageShape::Circle { radius } => StageShape::Circle { radius: radius * scale },
+      StageShape::Polygon { vertices } => StageShape::Polygon { vertices: scale_all(vertices) },
+      StageShape::Torus { size } => StageShape::Torus { size: size * scale },
+    };
+
+    StageConfig {
+      shape,
+      obstacles: self.obstacles.iter().map(scale_all).collect(),
+      food_placement: self.food_placement.scaled(scale),
+      ..self.clone()
+    }
+  }
+
+  // anything that would make no sense of a stage, by field, eg: `radius: must be more than 0, not -5`
+  pub fn problems(&self) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+    let positive = |field : &str, v : f64, problems : &mut Vec<Diagnostic>| {
+      if !(v.is_finite() && v > 0.) {
+        problems.push(Diagnostic::new(field, format!("must be more than 0, not {}", v)));
+      }
+    };
+    let finite = |field : &str, vertices : &Vec<(f64, f64)>, problems : &mut Vec<Diagnostic>| {
+      for (i, (x, y)) in vertices.iter().enumerate().filter(|(_, (x, y))| !(x.is_finite() && y.is_finite())) {
+        problems.push(Diagnostic::new(format!("{}[{}]", field, i), format!("must be numbers, not ({}, {})", x, y)));
+      }
+    };
+
+    match &self.shape {
+      StageShape::Square { size } | StageShape::Torus { size } => positive("size", *size, &mut problems),
+      StageShape::Circle { radius } => positive("radius", *radius, &mut problems),
+      StageShape::Polygon { vertices } => {
+        let before = problems.len();
+        finite("vertices", vertices, &mut problems);
+        if problems.len() == before {
+          if let Err(e) = PolygonStage::new(to_points(vertices)) {
+            problems.push(Diagnostic::new("vertices", e));
+          }
+        }
+      },
+    }
+
+    // only checked against a stage that makes sense
+    let stage = if problems.is_empty() { self.build_shape().ok() } else { None };
+    for (i, obstacle) in self.obstacles.iter().enumerate() {
+      let path = format!("obstacles[{}]", i);
+      let before = problems.len();
+      finite(&path, obstacle, &mut problems);
+      if obstacle.len() < 3 {
+        problems.push(Diagnostic::new(path.as_str(), format!("needs at least 3 vertices, not {}", obstacle.len())));
+      }
+
+      if let Some(stage) = stage.as_ref().filter(|_| problems.len() == before) {
+        for (j, v) in to_points(obstacle).iter().enumerate().filter(|(_, v)| !is_inside(&**stage, v)) {
+          problems.push(Diagnostic::new(format!("{}[{}]", path, j), format!("must be inside the stage, not ({}, {})", v.x, v.y)));
+        }
+      }
+    }
+
+    problems.extend(self.food_placement.problems().into_iter().map(|d| d.within("food_placement")));
+    problems.extend(self.food_dynamics.problems().into_iter().map(|d| d.within("food_dynamics")));
+    problems
+  }
+
+  pub fn build(&self) -> Result<Box<dyn Stage>, String> {
+    let problems = self.problems();
+    if !problems.is_empty() {
+      return Err(Diagnostic::join(&problems));
+    }
+
+    let stage = self.build_shape()?;
+    if self.obstacles.is_empty() {
+      return Ok(stage);
+    }
+
+    let obstacles = self.obstacles.iter().map(|o| to_points(o)).collect();
+    Ok(Box::new(ObstructedStage::new(stage, obstacles, self.obstacles_block_vision)?))
+  }
+
+  // without its obstacles
+  fn build_shape(&self) -> Result<Box<dyn Stage>, String> {
+    let stage : Box<dyn Stage> = match &self.shape {
+      StageShape::Square { size } => Box::new(SquareStage(*size)),
+      StageShape::Circle { radius } => Box::new(CircleStage(*radius)),
+      StageShape::Polygon { vertices } => Box::new(PolygonStage::new(to_points(vertices))?),
+      StageShape::Torus { size } => Box::new(TorusStage(*size)),
+    };
+
+    Ok(stage)
+  }
+}
+
+// on the stage, edges included
+fn is_inside(stage : &dyn Stage, p : &Point2<f64>) -> bool {
+  stage.constrain_within(p) == *p
+}
+
+mod circle;
+pub use circle::*;
+
+mod polygon;
+pub use polygon::*;
+
+mod obstructed;
+pub use obstructed::*;
+
+mod torus;
+pub use torus::*;
 
 // simple square
 pub struct SquareStage(pub f64);
@@ -43,7 +211,7 @@ impl Stage for SquareStage {
 
   fn get_center(&self) -> Point2<f64> { 0.5 * Point2::new(self.0, self.0) }
 
-  fn get_random_location(&self, rng : &mut RefMut<SmallRng>) -> Point2<f64> {
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
     let x = rng.gen_range(0., self.0);
     let y = rng.gen_range(0., self.0);
 
@@ -69,3 +237,78 @@ impl Stage for SquareStage {
     ret
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stage(shape : serde_json::Value) -> StageConfig {
+    serde_json::from_value(shape).unwrap()
+  }
+
+  fn problems(shape : serde_json::Value) -> Vec<String> {
+    stage(shape).problems().into_iter().map(|d| d.path).collect()
+  }
+
+  #[test]
+  fn stages_need_a_size() {
+    assert!(problems(serde_json::json!({ "shape": "square", "size": 500 })).is_empty());
+    assert_eq!(problems(serde_json::json!({ "shape": "square", "size": 0 })), vec!["size"]);
+    assert_eq!(problems(serde_json::json!({ "shape": "torus", "size": -1 })), vec!["size"]);
+    assert_eq!(problems(serde_json::json!({ "shape": "circle", "radius": -5 })), vec!["radius"]);
+  }
+
+  #[test]
+  fn polygons_need_an_area() {
+    assert!(problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [10, 0], [0, 10]] })).is_empty());
+    assert_eq!(problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [10, 0]] })), vec!["vertices"]);
+    assert_eq!(problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [10, 0], [20, 0]] })), vec!["vertices"]);
+    assert_eq!(problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [0, 10], [10, 0]] })), vec!["vertices"]);
+    // encloses an area, but crosses itself
+    assert_eq!(problems(serde_json::json!({ "shape": "polygon", "vertices": [[0, 0], [20, 0], [20, 20], [10, -5], [0, 20]] })), vec!["vertices"]);
+
+    let cfg = StageConfig {
+      shape: StageShape::Polygon { vertices: vec![(0., 0.), (f64::INFINITY, 0.), (0., 10.)] },
+      ..stage(serde_json::json!({ "shape": "square", "size": 1 }))
+    };
+    assert_eq!(cfg.problems().into_iter().map(|d| d.path).collect::<Vec<_>>(), vec!["vertices[1]"]);
+  }
+
+  #[test]
+  fn obstacles_need_three_vertices() {
+    let shape = serde_json::json!({ "shape": "square", "size": 500, "obstacles": [[[100, 100], [200, 100]]] });
+    assert_eq!(problems(shape), vec!["obstacles[0]"]);
+  }
+
+  #[test]
+  fn obstacles_must_be_inside_the_stage() {
+    let shape = serde_json::json!({ "shape": "square", "size": 500, "obstacles": [
+      [[100, 100], [200, 100], [150, 200]],
+      [[400, 400], [600, 400], [500, 450]],
+    ] });
+    assert_eq!(problems(shape.clone()), vec!["obstacles[1][1]"]);
+    assert!(stage(shape).build().is_err());
+
+    let circle = serde_json::json!({ "shape": "circle", "radius": 100, "obstacles": [[[1, 1], [20, 1], [10, 20]]] });
+    assert_eq!(problems(circle), vec!["obstacles[0][0]", "obstacles[0][1]", "obstacles[0][2]"]);
+  }
+
+  #[test]
+  fn nothing_starts_in_an_obstacle_on_the_edge() {
+    // all along the bottom edge
+    let shape = serde_json::json!({ "shape": "square", "size": 100, "obstacles": [[[0, 0], [100, 0], [100, 10], [0, 10]]] });
+    let stage = stage(shape).build().unwrap();
+    let rng = std::cell::RefCell::new(<SimRng as rand::SeedableRng>::seed_from_u64(6));
+    for _ in 0..200 {
+      let p = stage.get_random_edge_point(&mut rng.borrow_mut());
+      assert!(p.y > 0., "{} is in the obstacle", p);
+    }
+  }
+
+  #[test]
+  fn bad_stages_are_not_built() {
+    assert!(stage(serde_json::json!({ "shape": "square", "size": 0 })).build().is_err());
+    assert!(stage(serde_json::json!({ "shape": "torus", "size": -1 })).build().is_err());
+    assert!(stage(serde_json::json!({ "shape": "circle", "radius": -5 })).build().is_err());
+  }
+}
@@ -0,0 +1,148 @@
+use super::*;
+use crate::math::{closest_point_on_segment, segment_intersection};
+
+// how far short of a wall a creature stops
+const WALL_GAP : f64 = 1e-3;
+// give up looking for an unobstructed spot after this many tries
+const MAX_PLACEMENT_ATTEMPTS : usize = 10_000;
+
+// Wraps another stage with obstacles (rocks, lakes, walls) inside it.
+// Nothing spawns inside an obstacle, creatures slide along their walls,
+// and they can optionally block line of sight.
+pub struct ObstructedStage {
+  stage : Box<dyn Stage>,
+  obstacles : Vec<Vec<Point2<f64>>>,
+  blocks_vision : bool,
+}
+
+impl ObstructedStage {
+  pub fn new(stage : Box<dyn Stage>, obstacles : Vec<Vec<Point2<f64>>>, blocks_vision : bool) -> Result<Self, String> {
+    if obstacles.iter().any(|o| o.len() < 3) {
+      return Err(String::from("Obstacles need at least 3 vertices"));
+    }
+
+    if obstacles.iter().flatten().any(|v| !is_inside(&*stage, v)) {
+      return Err(String::from("Obstacles must be inside the stage"));
+    }
+
+    Ok(Self {
+      stage,
+      obstacles,
+      blocks_vision,
+    })
+  }
+
+  fn in_obstacle(&self, p : &Point2<f64>) -> bool {
+    self.obstacles.iter().any(|o| polygon_contains(o, p))
+  }
+
+  fn walls(&self) -> impl Iterator<Item = Edge> + '_ {
+    self.obstacles.iter().flat_map(|o| {
+      let n = o.len();
+      (0..n).map(move |i| Edge(o[i], o[(i + 1) % n]))
+    })
+  }
+
+  // the first wall hit going from -> to, and how far along the way it is
+  fn first_hit(&self, from : &Point2<f64>, to : &Point2<f64>) -> Option<(f64, Edge)> {
+    self.walls()
+      .filter_map(|w| segment_intersection(from, to, &w.0, &w.1).map(|t| (t, w)))
+      .min_by(|a, b| a.0.total_cmp(&b.0))
+  }
+
+  // furthest point towards `to` before hitting a wall
+  fn advance(&self, from : &Point2<f64>, to : &Point2<f64>) -> (Point2<f64>, Option<Edge>) {
+    match self.first_hit(from, to) {
+      None => (*to, None),
+      Some((t, wall)) => {
+        let len = (to - from).norm();
+        let t = if len > 0. { (t - WALL_GAP / len).max(0.) } else { 0. };
+        (from + t * (to - from), Some(wall))
+      },
+    }
+  }
+
+  fn nearest_wall_point(&self, pos : &Point2<f64>) -> Option<Point2<f64>> {
+    self.walls()
+      .map(|w| closest_point_on_segment(&w.0, &w.1, pos))
+      .map(|p| (p, (p - pos).norm()))
+      .min_by(|a, b| a.1.total_cmp(&b.1))
+      .map(|(p, _)| p)
+  }
+}
+
+impl Stage for ObstructedStage {
+
+  // homes are on the outer edges, not the obstacles
+  fn get_edges(&self) -> Vec<Edge> { self.stage.get_edges() }
+
+  fn can_move_to(&self, to : &Point2<f64>, creature : &Creature ) -> bool {
+    self.stage.can_move_to(to, creature) && !self.in_obstacle(to)
+  }
+
+  fn get_center(&self) -> Point2<f64> { self.stage.get_center() }
+
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    let mut p = self.stage.get_random_location(rng);
+    for _ in 1..MAX_PLACEMENT_ATTEMPTS {
+      if !self.in_obstacle(&p) { return p; }
+      p = self.stage.get_random_location(rng);
+    }
+
+    // practically all obstacle... fall back to the outer edge
+    self.stage.get_nearest_edge_point(&p)
+  }
+
+  fn get_nearest_edge_point(&self, pos : &Point2<f64>) -> Point2<f64> {
+    self.stage.get_nearest_edge_point(pos)
+  }
+
+  // obstacles can reach the edge, so try again if it's in one
+  fn get_random_edge_point(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    let mut p = self.stage.get_random_edge_point(rng);
+    for _ in 1..MAX_PLACEMENT_ATTEMPTS {
+      if !self.in_obstacle(&p) { return p; }
+      p = self.stage.get_random_edge_point(rng);
+    }
+
+    p
+  }
+
+  fn constrain_within(&self, pos : &Point2<f64>) -> Point2<f64> {
+    let p = self.stage.constrain_within(pos);
+    if self.in_obstacle(&p) {
+      self.nearest_wall_point(&p).unwrap_or(p)
+    } else {
+      p
+    }
+  }
+
+  fn resolve_move(&self, from : &Point2<f64>, to : &Point2<f64>) -> Point2<f64> {
+    let (stop, wall) = self.advance(from, to);
+    let end = match wall {
+      None => stop,
+      Some(wall) => {
+        // slide along the wall with whatever motion is left
+        let dir = (wall.1 - wall.0).normalize();
+        let slide_to = stop + (to - stop).dot(&dir) * dir;
+        self.advance(&stop, &slide_to).0
+      },
+    };
+
+    self.stage.constrain_within(&end)
+  }
+
+  fn has_line_of_sight(&self, from : &Point2<f64>, to : &Point2<f64>) -> bool {
+    !self.blocks_vision || self.first_hit(from, to).is_none()
+  }
+
+  fn nearest_image(&self, from : &Point2<f64>, to : &Point2<f64>) -> Point2<f64> {
+    self.stage.nearest_image(from, to)
+  }
+
+  fn wrap_period(&self) -> Option<f64> { self.stage.wrap_period() }
+
+  fn place(&self, pos : &Point2<f64>) -> Option<Point2<f64>> {
+    self.stage.place(pos).filter(|p| !self.in_obstacle(p))
+  }
+}
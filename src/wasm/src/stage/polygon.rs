@@ -0,0 +1,166 @@
+use super::*;
+use crate::math::{closest_point_on_segment, segment_intersection};
+
+// give up on rejection sampling after this many misses
+const MAX_PLACEMENT_ATTEMPTS : usize = 10_000;
+
+// is the point inside the polygon? (even-odd rule)
+pub fn polygon_contains(vertices : &[Point2<f64>], p : &Point2<f64>) -> bool {
+  let n = vertices.len();
+  let mut inside = false;
+  for i in 0..n {
+    let a = vertices[i];
+    let b = vertices[(i + 1) % n];
+    if (a.y > p.y) != (b.y > p.y) {
+      let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+      if p.x < x {
+        inside = !inside;
+      }
+    }
+  }
+  inside
+}
+
+// do any two edges that aren't next to each other cross?
+fn crosses_itself(vertices : &[Point2<f64>]) -> bool {
+  let n = vertices.len();
+  let edge = |i : usize| (vertices[i], vertices[(i + 1) % n]);
+  (0..n).any(|i| {
+    // the last edge is next to the first
+    let last = if i == 0 { n - 1 } else { n };
+    (i + 2..last).any(|j| {
+      let (a, b) = edge(i);
+      let (c, d) = edge(j);
+      segment_intersection(&a, &b, &c, &d).is_some()
+    })
+  })
+}
+
+// arbitrary simple polygon, vertices given counter clockwise
+pub struct PolygonStage {
+  vertices : Vec<Point2<f64>>,
+  min : Point2<f64>,
+  max : Point2<f64>,
+  centroid : Point2<f64>,
+}
+
+impl PolygonStage {
+  pub fn new(vertices : Vec<Point2<f64>>) -> Result<Self, String> {
+    if vertices.len() < 3 {
+      return Err(String::from("A polygon stage needs at least 3 vertices"));
+    }
+
+    // shoelace formula. positive when counter clockwise
+    let n = vertices.len();
+    let (mut area, mut cx, mut cy) = (0., 0., 0.);
+    for i in 0..n {
+      let a = vertices[i];
+      let b = vertices[(i + 1) % n];
+      let cross = a.x * b.y - b.x * a.y;
+      area += cross;
+      cx += (a.x + b.x) * cross;
+      cy += (a.y + b.y) * cross;
+    }
+    area *= 0.5;
+
+    if area.is_nan() || area <= 0. {
+      return Err(String::from("Polygon stage vertices must be counter clockwise and enclose an area"));
+    }
+
+    if crosses_itself(&vertices) {
+      return Err(String::from("Polygon stage edges must not cross each other"));
+    }
+
+    let min = vertices.iter().fold(vertices[0], |m, v| Point2::new(m.x.min(v.x), m.y.min(v.y)));
+    let max = vertices.iter().fold(vertices[0], |m, v| Point2::new(m.x.max(v.x), m.y.max(v.y)));
+
+    Ok(Self {
+      centroid: Point2::new(cx / (6. * area), cy / (6. * area)),
+      vertices,
+      min,
+      max,
+    })
+  }
+
+  fn contains(&self, p : &Point2<f64>) -> bool {
+    polygon_contains(&self.vertices, p)
+  }
+}
+
+impl Stage for PolygonStage {
+
+  fn get_edges(&self) -> Vec<Edge> {
+    let n = self.vertices.len();
+    (0..n).map(|i| {
+      Edge(self.vertices[i], self.vertices[(i + 1) % n])
+    }).collect()
+  }
+
+  fn can_move_to(&self, to : &Point2<f64>, _creature : &Creature ) -> bool {
+    self.contains(to)
+  }
+
+  fn get_center(&self) -> Point2<f64> { self.centroid }
+
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    // uniform over the bounding box, keeping only what lands inside
+    for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+      let x = rng.gen_range(self.min.x, self.max.x);
+      let y = rng.gen_range(self.min.y, self.max.y);
+      let p = Point2::new(x, y);
+
+      if self.contains(&p) {
+        return p;
+      }
+    }
+
+    self.centroid
+  }
+
+  fn get_nearest_edge_point(&self, pos : &Point2<f64>) -> Point2<f64> {
+    // nowhere is nearest to a point that isn't anywhere
+    if !(pos.x.is_finite() && pos.y.is_finite()) {
+      return self.centroid;
+    }
+
+    self.get_edges().iter()
+      .map(|e| closest_point_on_segment(&e.0, &e.1, pos))
+      .map(|p| (p, (p - pos).norm()))
+      .min_by(|a, b| a.1.total_cmp(&b.1))
+      .map_or(self.centroid, |(p, _)| p)
+  }
+
+  fn constrain_within(&self, pos : &Point2<f64>) -> Point2<f64> {
+    if self.contains(pos) {
+      *pos
+    } else {
+      self.get_nearest_edge_point(pos)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn square() -> PolygonStage {
+    let vertices = vec![Point2::new(0., 0.), Point2::new(500., 0.), Point2::new(500., 500.), Point2::new(0., 500.)];
+    PolygonStage::new(vertices).unwrap()
+  }
+
+  #[test]
+  fn nearest_edge_point_is_on_the_nearest_edge() {
+    let stage = square();
+    assert_eq!(stage.get_nearest_edge_point(&Point2::new(10., 200.)), Point2::new(0., 200.));
+    assert_eq!(stage.get_nearest_edge_point(&Point2::new(600., 450.)), Point2::new(500., 450.));
+  }
+
+  #[test]
+  fn positions_that_arent_anywhere_dont_panic() {
+    let stage = square();
+    for pos in [Point2::new(f64::NAN, 1.), Point2::new(1., f64::INFINITY)].iter() {
+      assert_eq!(stage.get_nearest_edge_point(pos), Point2::new(250., 250.));
+      assert_eq!(stage.constrain_within(pos), Point2::new(250., 250.));
+    }
+  }
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+// Square with no boundary. Anything leaving one side comes back on
+// the opposite one, and distances are measured to the nearest image.
+pub struct TorusStage(pub f64);
+
+impl TorusStage {
+  fn wrap(&self, v : f64) -> f64 {
+    let w = v.rem_euclid(self.0);
+    // rounding can land exactly on the far side
+    if w >= self.0 { 0. } else { w }
+  }
+}
+
+impl Stage for TorusStage {
+
+  // no edges at all
+  fn get_edges(&self) -> Vec<Edge> { vec![] }
+
+  fn can_move_to(&self, _to : &Point2<f64>, _creature : &Creature ) -> bool { true }
+
+  fn get_center(&self) -> Point2<f64> { 0.5 * Point2::new(self.0, self.0) }
+
+  fn get_random_location(&self, rng : &mut RefMut<SimRng>) -> Point2<f64> {
+    let x = rng.gen_range(0., self.0);
+    let y = rng.gen_range(0., self.0);
+
+    Point2::new(x, y)
+  }
+
+  // there is no edge, so stay put
+  fn get_nearest_edge_point(&self, pos : &Point2<f64>) -> Point2<f64> {
+    self.constrain_within(pos)
+  }
+
+  fn constrain_within(&self, pos : &Point2<f64>) -> Point2<f64> {
+    Point2::new(self.wrap(pos.x), self.wrap(pos.y))
+  }
+
+  fn nearest_image(&self, from : &Point2<f64>, to : &Point2<f64>) -> Point2<f64> {
+    let mut d = to - from;
+    d.x -= self.0 * (d.x / self.0).round();
+    d.y -= self.0 * (d.y / self.0).round();
+    from + d
+  }
+
+  fn wrap_period(&self) -> Option<f64> { Some(self.0) }
+
+  // anywhere goes, it just wraps around
+  fn place(&self, pos : &Point2<f64>) -> Option<Point2<f64>> {
+    Some(self.constrain_within(pos))
+  }
+}
@@ -40,22 +40,97 @@ async function run( method, ...args ){
 // API
 // ---------------------------------------
 let simulation = null
+
+// like run(), for methods of the simulation
+function call( method, ...args ){
+  try {
+    return simulation[method].apply( simulation, args )
+  } catch( msg ){
+    let err = new Error( msg )
+    logErr(`Worker: ERROR from ${method}`, err)
+    throw err
+  }
+}
 export async function initSimulation( cfg, creatureCfgs = [] ){
   const wasm = await app
-  simulation = wasm.SquareWorld.new(
-    cfg.size
+  simulation = wasm.World.new(
+    cfg.stage
     , cfg.seed
     , cfg.food_per_generation
     , cfg.preset
   )
 
+  if ( cfg.species ){
+    simulation.set_species(cfg.species)
+  }
+
+  if ( cfg.events ){
+    simulation.set_events(cfg.events)
+  }
+
   creatureCfgs.forEach(cfg => {
     simulation.add_creatures(cfg)
   })
 }
 
+// the whole config, as the simulation takes it
+function worldConfig( cfg, creatureCfgs ){
+  return {
+    stage: cfg.stage
+    , seed: cfg.seed
+    , food_per_generation: cfg.food_per_generation
+    , preset: cfg.preset
+    , creatures: creatureCfgs
+    , species: cfg.species
+    , events: cfg.events
+    , max_generations: cfg.max_generations
+  }
+}
+
+// every problem with the config, by path
+export async function validateConfig( cfg, creatureCfgs = [] ){
+  const wasm = await app
+  return wasm.validate_config(worldConfig(cfg, creatureCfgs))
+}
+
+// the config from a scenario file of any version. format is 'json' or 'toml'
+export function loadScenario( text, format = 'json' ){
+  return run('load_scenario', text, format)
+}
+
+export function saveScenario( cfg, creatureCfgs = [], format = 'json' ){
+  return run('save_scenario', worldConfig(cfg, creatureCfgs), format)
+}
+
+export async function restoreSimulation( snapshot ){
+  const wasm = await app
+  simulation = wasm.World.restore( snapshot )
+}
+
+export function getSnapshot(){
+  return simulation.snapshot()
+}
+
 export function advanceSimulation( numGens ){
-  return simulation.run( numGens )
+  // eg: a runaway generation
+  return call('run', numGens)
+}
+
+export function startGeneration(){
+  return simulation.start_generation()
+}
+
+// returns true once the generation is over
+export function advanceSteps( numSteps ){
+  return call('advance', numSteps)
+}
+
+export function intervene( intervention ){
+  return call('intervene', intervention)
+}
+
+export function getGenerationInProgress(){
+  return simulation.get_generation_in_progress()
 }
 
 export function canContinue(){
@@ -67,9 +142,17 @@ export function getResults(){
 }
 
 export function getGeneration(index){
-  return simulation.get_generation(index)
+  return call('get_generation', index)
 }
 
 export function getStatistics(species){
   return simulation.get_statistics(species)
 }
+
+export function scheduleInvasion(generation, cfg){
+  return simulation.schedule_invasion(generation, cfg)
+}
+
+export function getInvasions(){
+  return simulation.get_invasions()
+}